@@ -0,0 +1,90 @@
+use trek_lang::lexer::{FileId, LineIndex, Span};
+use super::{LexerError, LexerErrorType, Token, TokenKind};
+
+/// A position in the source being lexed. Cloning it is cheap, which is how
+/// the sub-lexers look ahead without consuming anything.
+#[derive(Clone)]
+pub struct Cursor<'a> {
+    source: &'a str,
+    offset: usize,
+    file: FileId,
+    lines: &'a LineIndex,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(source: &'a str, file: FileId, lines: &'a LineIndex) -> Self {
+        Cursor { source, offset: 0, file, lines }
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    pub fn peek_second(&self) -> Option<char> {
+        self.rest().chars().nth(1)
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.source[self.offset..]
+    }
+
+    /// The source text from `start` up to the current position.
+    pub fn slice_from(&self, start: usize) -> &'a str {
+        &self.source[start..self.offset]
+    }
+
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        Span::new(self.file, start as u32, end as u32)
+    }
+
+    pub fn span_from(&self, start: usize) -> Span {
+        self.span(start, self.offset)
+    }
+
+    /// A token covering everything from `start` up to the current position.
+    pub fn token(&self, kind: TokenKind, value: String, start: usize) -> Token {
+        self.token_between(kind, value, start, self.offset)
+    }
+
+    /// A token covering the source text `start..end`.
+    pub fn token_between(&self, kind: TokenKind, value: String, start: usize, end: usize) -> Token {
+        let position = self.lines.line_col(start as u32);
+        Token {
+            kind,
+            text: self.source[start..end].to_string(),
+            value,
+            span: self.span(start, end),
+            line: position.line as usize,
+            column: position.column as usize,
+        }
+    }
+
+    /// An error covering everything from `start` up to the current position.
+    pub fn error(&self, error_type: LexerErrorType, start: usize) -> LexerError {
+        self.error_at(error_type, self.span_from(start))
+    }
+
+    pub fn error_at(&self, error_type: LexerErrorType, span: Span) -> LexerError {
+        let position = self.lines.line_col(span.start);
+        LexerError {
+            error_type,
+            span,
+            line: position.line as usize,
+            column: position.column as usize,
+        }
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+}
@@ -0,0 +1,299 @@
+//! The eager lexer as it was before `Lexer` streamed tokens, pinned here so
+//! the bench compares the streaming lexer with the path it replaced. It
+//! takes an owned `String`, allocates a `String` per token and builds,
+//! filters and reverses a full `Vec`. Only the default options the bench
+//! lexes with are kept, and numbers and strings are lexed by the modules in
+//! `src/lexer` that the streaming lexer shares.
+
+mod cursor;
+// the parser's half of the number helpers goes unused here
+#[allow(dead_code)]
+#[path = "../../src/lexer/number.rs"]
+mod number;
+#[path = "../../src/lexer/string.rs"]
+mod string;
+
+use trek_lang::lexer::{FileId, Keyword, LineIndex, Span};
+
+use cursor::Cursor;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexerErrorType {
+    UnexpectedCharacter(char),
+    /// A `/*` comment that is never closed; the error points at the `/*`.
+    UnterminatedBlockComment,
+    /// A radix prefix such as `0x` that is not followed by any digits.
+    MissingDigits,
+    /// A digit that is not valid in the literal's radix, as in `0b102`.
+    InvalidDigit { digit: char, radix: u32 },
+    /// Letters after a number that are not one of the type suffixes.
+    InvalidNumberSuffix(String),
+    /// A string that reaches the end of input; the error points at the opening quote.
+    UnterminatedString,
+    /// A `\` followed by a character that is not a known escape.
+    InvalidEscape(char),
+    /// A `\u` escape that is malformed or names something other than a Unicode scalar value.
+    InvalidUnicodeEscape,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexerError {
+    error_type: LexerErrorType,
+    span: Span,
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Integer,
+    Float,
+    Operator,
+    Symbol,
+    Keyword(Keyword),
+    NewLine,
+    StringLiteral,
+    /// Opening quote of a string containing `${...}` holes. It is followed by
+    /// `StringFragment`s and `HoleStart` ... `HoleEnd` runs of ordinary
+    /// tokens, and closed by `InterpolationEnd`.
+    InterpolationStart,
+    StringFragment,
+    HoleStart,
+    HoleEnd,
+    InterpolationEnd,
+    /// Input the lexer could not make sense of. A matching `LexerError`
+    /// explains why.
+    Error,
+}
+
+/// A token and where it came from. `span` is the exact byte range of the
+/// token's source text `text`, and `value` is what that text means, with
+/// escapes resolved. `line` and `column` locate its start, with the column
+/// counted in UTF-8 bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    pub value: String,
+    pub span: Span,
+    pub column: usize,
+    pub line: usize,
+}
+
+/// Every operator and punctuation token, longest first so that the first
+/// match found by `longest_operator` is the maximal munch.
+const OPERATORS: &[&str] = &[
+    "==", "=>", "!=", "<=", ">=", "&&", "||", "|>",
+    "+", "-", "*", "/", "%", "=", "!", "<", ">",
+    ".", ",", ":", ";", "(", ")", "{", "}", "[", "]",
+];
+
+fn longest_operator(cursor: &Cursor) -> Option<&'static str> {
+    OPERATORS.iter().copied().find(|op| cursor.rest().starts_with(op))
+}
+
+/// What the lexer is in the middle of when strings and holes nest.
+enum Mode {
+    /// Inside the quotes of a string whose opening quote is at `start`.
+    String { quote: char, start: usize, interpolated: bool },
+    /// Inside a `${...}` hole, with `depth` unclosed `{` of its own.
+    Hole { depth: usize },
+}
+
+/// The tokens and errors lexed so far.
+struct Output {
+    tokens: Vec<Token>,
+    errors: Vec<LexerError>,
+}
+
+impl Output {
+    /// Records `error` and covers the input from `start` up to the cursor
+    /// with an `Error` token so the token stream still accounts for it.
+    fn recover(&mut self, cursor: &Cursor, start: usize, error: LexerError) {
+        self.errors.push(error);
+        self.tokens.push(cursor.token(TokenKind::Error, cursor.slice_from(start).to_string(), start));
+    }
+}
+
+/// Lexes the whole input, carrying on past bad input so that every problem
+/// in the file is reported at once. The tokens are returned in reverse order
+/// for the parser to pop from.
+pub fn tokenize(input: String) -> (Vec<Token>, Vec<LexerError>) {
+    let lines = LineIndex::new(&input);
+    let mut cursor = Cursor::new(&input, FileId::default(), &lines);
+    let mut output = Output { tokens: Vec::new(), errors: Vec::new() };
+    let mut modes: Vec<Mode> = Vec::new();
+
+    loop {
+        if let Some(Mode::String { quote, start, interpolated }) = modes.last_mut() {
+            let fragment_start = cursor.offset();
+            let (value, end) = match string::lex_fragment(&mut cursor, *quote, *start, &mut output.errors) {
+                Ok(fragment) => fragment,
+                Err(error) => {
+                    let error_start = if *interpolated { fragment_start } else { *start };
+                    output.recover(&cursor, error_start, error);
+                    modes.clear();
+                    continue;
+                }
+            };
+            if end == string::FragmentEnd::Closed && !*interpolated {
+                output.tokens.push(cursor.token(TokenKind::StringLiteral, value, *start));
+                modes.pop();
+                continue;
+            }
+            if !*interpolated {
+                output.tokens.push(cursor.token_between(TokenKind::InterpolationStart, quote.to_string(), *start, fragment_start));
+                *interpolated = true;
+            }
+            let delimiter_start = match end {
+                string::FragmentEnd::Closed => cursor.offset() - 1,
+                string::FragmentEnd::Hole => cursor.offset() - 2,
+            };
+            if !value.is_empty() {
+                output.tokens.push(cursor.token_between(TokenKind::StringFragment, value, fragment_start, delimiter_start));
+            }
+            match end {
+                string::FragmentEnd::Closed => {
+                    output.tokens.push(cursor.token(TokenKind::InterpolationEnd, quote.to_string(), delimiter_start));
+                    modes.pop();
+                }
+                string::FragmentEnd::Hole => {
+                    output.tokens.push(cursor.token(TokenKind::HoleStart, "${".to_string(), delimiter_start));
+                    modes.push(Mode::Hole { depth: 0 });
+                }
+            }
+            continue;
+        }
+
+        let start = cursor.offset();
+        let c = match cursor.peek() {
+            Some(c) => c,
+            None => break,
+        };
+        match c {
+            '0'..='9' => {
+                match number::lex_number(&mut cursor) {
+                    Ok(kind) => {
+                        output.tokens.push(cursor.token(kind, cursor.slice_from(start).to_string(), start));
+                    }
+                    Err(error) => {
+                        // skip the rest of the literal so it is not lexed again as a new token
+                        while cursor.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+                            cursor.next();
+                        }
+                        output.recover(&cursor, start, error);
+                    }
+                }
+            }
+            '/' if cursor.peek_second() == Some('/') => {
+                while cursor.peek().is_some_and(|c| c != '\n' && c != '\r') {
+                    cursor.next();
+                }
+            }
+            '/' if cursor.peek_second() == Some('*') => {
+                cursor.next();
+                cursor.next();
+                // block comments nest, so track how many are still open
+                let mut depth = 1;
+                while depth > 0 {
+                    match cursor.next() {
+                        Some('/') if cursor.peek() == Some('*') => {
+                            cursor.next();
+                            depth += 1;
+                        }
+                        Some('*') if cursor.peek() == Some('/') => {
+                            cursor.next();
+                            depth -= 1;
+                        }
+                        Some(_) => {}
+                        None => {
+                            let opening = cursor.span(start, start + 2);
+                            output.errors.push(cursor.error_at(LexerErrorType::UnterminatedBlockComment, opening));
+                            break;
+                        }
+                    }
+                }
+            }
+            '}' if matches!(modes.last(), Some(Mode::Hole { depth: 0 })) => {
+                cursor.next();
+                output.tokens.push(cursor.token(TokenKind::HoleEnd, "}".to_string(), start));
+                modes.pop();
+            }
+            c if OPERATORS.iter().any(|op| op.starts_with(c)) => {
+                let op = match longest_operator(&cursor) {
+                    Some(op) => op,
+                    None => {
+                        cursor.next();
+                        let error = cursor.error(LexerErrorType::UnexpectedCharacter(c), start);
+                        output.recover(&cursor, start, error);
+                        continue;
+                    }
+                };
+                if let Some(Mode::Hole { depth }) = modes.last_mut() {
+                    match op {
+                        "{" => *depth += 1,
+                        "}" => *depth -= 1,
+                        _ => {}
+                    }
+                }
+                for _ in 0..op.len() {
+                    cursor.next();
+                }
+                output.tokens.push(cursor.token(TokenKind::Operator, op.to_string(), start));
+            }
+            'r' if matches!(cursor.peek_second(), Some('\'' | '"' | '#')) => {
+                match string::lex_raw(&mut cursor) {
+                    Ok(value) => output.tokens.push(cursor.token(TokenKind::StringLiteral, value, start)),
+                    Err(error) => output.recover(&cursor, start, error),
+                }
+            }
+            c if c == '_' || c.is_ascii_alphabetic() => {
+                while cursor.peek().is_some_and(|c| c == '_' || c.is_ascii_alphanumeric()) {
+                    cursor.next();
+                }
+                let value = cursor.slice_from(start).to_string();
+                let kind = match Keyword::lookup(&value) {
+                    Some(keyword) => TokenKind::Keyword(keyword),
+                    None => TokenKind::Symbol,
+                };
+                output.tokens.push(cursor.token(kind, value, start));
+            }
+            '"' | '\'' if cursor.rest().chars().take(3).filter(|next| *next == c).count() == 3 => {
+                match string::lex_triple_quoted(&mut cursor, &mut output.errors) {
+                    Ok(value) => output.tokens.push(cursor.token(TokenKind::StringLiteral, value, start)),
+                    Err(error) => output.recover(&cursor, start, error),
+                }
+            }
+            '"' | '\'' => {
+                cursor.next();
+                modes.push(Mode::String { quote: c, start, interpolated: false });
+            }
+            '\n' | '\r' => {
+                cursor.next();
+                if c == '\r' && cursor.peek() == Some('\n') {
+                    cursor.next();
+                }
+                output.tokens.push(cursor.token(TokenKind::NewLine, "\n".to_string(), start));
+            }
+            ' ' | '\t' => {
+                while cursor.peek().is_some_and(|c| c == ' ' || c == '\t') {
+                    cursor.next();
+                }
+            }
+            _ => {
+                cursor.next();
+                let error = cursor.error(LexerErrorType::UnexpectedCharacter(c), start);
+                output.recover(&cursor, start, error);
+            }
+        }
+    }
+
+    // a hole can only be left open at EOF inside an interpolated string
+    if let Some(Mode::String { start, .. }) = modes.into_iter().rev().find(|mode| matches!(mode, Mode::String { .. })) {
+        let opening = cursor.span(start, start + 1);
+        output.errors.push(cursor.error_at(LexerErrorType::UnterminatedString, opening));
+    }
+
+    let tokens = output.tokens.into_iter().filter(|t| t.kind != TokenKind::NewLine).rev().collect();
+    (tokens, output.errors)
+}
@@ -0,0 +1,45 @@
+mod eager;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use trek_lang::{lexer, parse};
+
+/// A few megabytes of generated source exercising every kind of token.
+fn generated_source() -> String {
+    let chunk = r#"
+// helpers generated for case {n}
+let add_{n} = (a b) => add(a, b)
+let scale_{n} = (x) => {
+    /* nested /* comments */ too */
+    mul(x, 1_000u32)
+}
+let greet_{n} = (name) => 'Hello ${name}, you are ${age(name)} today'
+let raw_{n} = r#'C:\path\to\file'#
+let check_{n} = if true { 0x1F } else { 2.5e-3f64 }
+"#;
+    (0..20_000).map(|n| chunk.replace("{n}", &n.to_string())).collect()
+}
+
+fn lex(c: &mut Criterion) {
+    let source = generated_source();
+    let mut group = c.benchmark_group("lex");
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.sample_size(20);
+    // the eager lexer takes ownership of its input, so each run gets a copy
+    // made outside the timing
+    group.bench_function("eager", |b| b.iter_batched(|| source.clone(), eager::tokenize, BatchSize::LargeInput));
+    group.bench_function("tokenize", |b| b.iter(|| lexer::tokenize(black_box(&source))));
+    group.bench_function("lexer", |b| b.iter(|| lexer::Lexer::new(black_box(&source)).count()));
+    group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+    let source = generated_source();
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.sample_size(10);
+    group.bench_function("parse_module", |b| b.iter(|| parse::parse_module(lexer::Lexer::new(black_box(&source)))));
+    group.finish();
+}
+
+criterion_group!(benches, lex, parse);
+criterion_main!(benches);
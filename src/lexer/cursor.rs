@@ -0,0 +1,115 @@
+use std::borrow::Cow;
+
+use super::span::{FileId, Span};
+use super::{LexerError, LexerErrorType, Token, TokenKind, Trivia, TriviaKind};
+
+/// A position in the source being lexed. Cloning it is cheap, which is how
+/// the sub-lexers look ahead without consuming anything.
+///
+/// The cursor only deals in byte offsets; the `Lexer` fills in the line and
+/// column of the tokens and errors it makes.
+#[derive(Clone)]
+pub struct Cursor<'a> {
+    source: &'a str,
+    offset: usize,
+    file: FileId,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(source: &'a str, file: FileId) -> Self {
+        Cursor { source, offset: 0, file }
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    pub fn peek_second(&self) -> Option<char> {
+        self.rest().chars().nth(1)
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Moves the cursor to `offset`, which must be on a character boundary.
+    pub fn seek(&mut self, offset: usize) {
+        self.offset = offset;
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.source[self.offset..]
+    }
+
+    /// The source text from `start` up to the current position.
+    pub fn slice_from(&self, start: usize) -> &'a str {
+        &self.source[start..self.offset]
+    }
+
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        Span::new(self.file, start as u32, end as u32)
+    }
+
+    pub fn span_from(&self, start: usize) -> Span {
+        self.span(start, self.offset)
+    }
+
+    /// A token covering everything from `start` up to the current position,
+    /// whose value is its source text.
+    pub fn token(&self, kind: TokenKind, start: usize) -> Token<'a> {
+        self.token_between(kind, start, self.offset)
+    }
+
+    /// A token covering the source text `start..end`.
+    pub fn token_between(&self, kind: TokenKind, start: usize, end: usize) -> Token<'a> {
+        let text = &self.source[start..end];
+        Token {
+            kind,
+            text,
+            value: Cow::Borrowed(text),
+            symbol: None,
+            span: self.span(start, end),
+            line: 0,
+            column: 0,
+            leading_trivia: Vec::new(),
+            trailing_trivia: Vec::new(),
+        }
+    }
+
+    /// Trivia covering everything from `start` up to the current position.
+    pub fn trivia(&self, kind: TriviaKind, start: usize) -> Trivia<'a> {
+        Trivia {
+            kind,
+            text: self.slice_from(start),
+            span: self.span_from(start),
+        }
+    }
+
+    /// An error covering everything from `start` up to the current position.
+    pub fn error(&self, error_type: LexerErrorType, start: usize) -> LexerError {
+        self.error_at(error_type, self.span_from(start))
+    }
+
+    pub fn error_at(&self, error_type: LexerErrorType, span: Span) -> LexerError {
+        LexerError {
+            error_type,
+            span,
+            line: 0,
+            column: 0,
+        }
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+}
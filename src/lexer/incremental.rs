@@ -0,0 +1,316 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use super::{ends_expression, track_brackets, Declaring, Lexer, LexerError, LexerOptions, LineIndex, Token, TokenKind, Trivia};
+
+/// How far past the end of a token the lexer may look to decide where the
+/// token ends, as in `1.` followed by a digit or `/` followed by `*`.
+const LOOKAHEAD: usize = 2;
+
+/// A change to a source text: the bytes in `range` are replaced by
+/// `new_text`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub range: Range<u32>,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<u32>, new_text: impl Into<String>) -> Self {
+        TextEdit { range, new_text: new_text.into() }
+    }
+
+    /// The text `source` becomes after the edit.
+    pub fn apply(&self, source: &str) -> String {
+        let (start, end) = (self.range.start as usize, self.range.end as usize);
+        [&source[..start], &self.new_text, &source[end..]].concat()
+    }
+
+    /// How far text after the edit moves.
+    fn delta(&self) -> i64 {
+        self.new_text.len() as i64 - (self.range.end - self.range.start) as i64
+    }
+}
+
+/// What the lexer needs to know to carry on after a token.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct State {
+    brackets: Vec<char>,
+    ends_expression: bool,
+    declaring: Declaring,
+    /// How many interpolated strings are open. The lexer is only restarted
+    /// or resynchronised outside of them.
+    strings: usize,
+}
+
+impl State {
+    fn after(&mut self, token: &Token) {
+        track_brackets(&mut self.brackets, token);
+        self.ends_expression = ends_expression(token);
+        self.declaring = self.declaring.after(token);
+        match token.kind {
+            TokenKind::InterpolationStart => self.strings += 1,
+            TokenKind::InterpolationEnd => self.strings -= 1,
+            _ => {}
+        }
+    }
+}
+
+/// The start of `token` including its leading trivia.
+fn extent_start(token: &Token) -> usize {
+    token.leading_trivia.first().map_or(token.span.start, |trivia| trivia.span.start) as usize
+}
+
+/// The end of `token` including its trailing trivia.
+fn extent_end(token: &Token) -> usize {
+    token.trailing_trivia.last().map_or(token.span.end, |trivia| trivia.span.end) as usize
+}
+
+/// Updates `old`, the tokens and errors lexed from a source text, for
+/// `edit`, which turned that text into `new_source`. Only the tokens around
+/// the edit are lexed again: lexing restarts at the last token boundary
+/// safely before the edit and stops as soon as it produces a token that
+/// matches an old one after the edit in the same lexer state. The old tokens
+/// from there on are reused with their spans shifted.
+///
+/// `old` is what `tokenize_with_options` returned for the old text with the
+/// same `options`, in source order, and the result is what it would return
+/// for `new_source`.
+pub fn relex<'new>(
+    old: &[Token],
+    old_errors: &[LexerError],
+    edit: &TextEdit,
+    new_source: &'new str,
+    options: &LexerOptions,
+) -> (Vec<Token<'new>>, Vec<LexerError>) {
+    let lines = LineIndex::new(new_source);
+    let edit_start = edit.range.start as usize;
+    let delta = edit.delta();
+
+    // keep every token that lexing could not have been affected by the edit
+    let mut kept = 0;
+    let mut restart = 0;
+    let mut restart_state = State::default();
+    let mut state = State::default();
+    for (index, token) in old.iter().enumerate() {
+        if extent_end(token) + LOOKAHEAD > edit_start {
+            break;
+        }
+        state.after(token);
+        if state.strings == 0 {
+            kept = index + 1;
+            restart = extent_end(token);
+            restart_state = state.clone();
+        }
+    }
+
+    let mut tokens: Vec<Token<'new>> = old[..kept].iter().map(|token| shift(token, new_source, &lines, 0)).collect();
+    let mut errors: Vec<LexerError> = old_errors
+        .iter()
+        .filter(|error| (error.span.start as usize) < restart)
+        .cloned()
+        .collect();
+
+    let mut lexer = Lexer::with_options(new_source, options);
+    lexer.cursor.seek(restart);
+    lexer.brackets = restart_state.brackets.clone();
+    lexer.ends_expression = restart_state.ends_expression;
+    lexer.declaring = restart_state.declaring;
+
+    let edit_end = (edit_start + edit.new_text.len()) as i64;
+    let mut old_index = kept;
+    let mut old_state = restart_state;
+    while let Some(item) = lexer.next() {
+        let token = match item {
+            Ok(token) => token,
+            Err(error) => {
+                errors.push(error);
+                continue;
+            }
+        };
+        let start = token.span.start as i64;
+        // the lexer state only matches the token once nothing else is queued
+        if start < edit_end || !lexer.queue.is_empty() || !lexer.modes.is_empty() {
+            tokens.push(token);
+            continue;
+        }
+        while old_index < old.len() && (old[old_index].span.start as i64) < start - delta {
+            old_state.after(&old[old_index]);
+            old_index += 1;
+        }
+        let resynced = old.get(old_index).is_some_and(|old_token| {
+            // the old token and its trivia must lie wholly after the edit
+            if old_token.span.start as i64 != start - delta || extent_start(old_token) < edit.range.end as usize {
+                return false;
+            }
+            let mut state = old_state.clone();
+            state.after(old_token);
+            let new_state = State {
+                brackets: lexer.brackets.clone(),
+                ends_expression: lexer.ends_expression,
+                declaring: lexer.declaring,
+                strings: 0,
+            };
+            state == new_state && shift(old_token, new_source, &lines, delta) == token
+        });
+        tokens.push(token);
+        if resynced {
+            let resync_end = extent_end(&old[old_index]);
+            tokens.extend(old[old_index + 1..].iter().map(|token| shift(token, new_source, &lines, delta)));
+            errors.extend(
+                old_errors
+                    .iter()
+                    .filter(|error| error.span.start as usize >= resync_end)
+                    .map(|error| shift_error(error, &lines, delta)),
+            );
+            break;
+        }
+    }
+    (tokens, errors)
+}
+
+fn shift_offset(offset: u32, delta: i64) -> u32 {
+    (offset as i64 + delta) as u32
+}
+
+/// `token` moved by `delta` bytes, borrowing from `new_source` instead.
+fn shift<'new>(token: &Token, new_source: &'new str, lines: &LineIndex, delta: i64) -> Token<'new> {
+    let mut span = token.span;
+    span.start = shift_offset(span.start, delta);
+    span.end = shift_offset(span.end, delta);
+    let text = &new_source[span.start as usize..span.end as usize];
+    let value = match &token.value {
+        // a borrowed value is always a slice of the token's own text
+        Cow::Borrowed(value) => {
+            let offset = value.as_ptr() as usize - token.text.as_ptr() as usize;
+            Cow::Borrowed(&text[offset..offset + value.len()])
+        }
+        Cow::Owned(value) => Cow::Owned(value.clone()),
+    };
+    let position = lines.line_col(span.start);
+    Token {
+        kind: token.kind.clone(),
+        text,
+        value,
+        symbol: token.symbol,
+        span,
+        line: position.line as usize,
+        column: position.column as usize,
+        leading_trivia: shift_trivia(&token.leading_trivia, new_source, delta),
+        trailing_trivia: shift_trivia(&token.trailing_trivia, new_source, delta),
+    }
+}
+
+fn shift_trivia<'new>(trivia: &[Trivia], new_source: &'new str, delta: i64) -> Vec<Trivia<'new>> {
+    trivia
+        .iter()
+        .map(|trivia| {
+            let mut span = trivia.span;
+            span.start = shift_offset(span.start, delta);
+            span.end = shift_offset(span.end, delta);
+            Trivia {
+                kind: trivia.kind,
+                text: &new_source[span.start as usize..span.end as usize],
+                span,
+            }
+        })
+        .collect()
+}
+
+fn shift_error(error: &LexerError, lines: &LineIndex, delta: i64) -> LexerError {
+    let mut span = error.span;
+    span.start = shift_offset(span.start, delta);
+    span.end = shift_offset(span.end, delta);
+    let position = lines.line_col(span.start);
+    LexerError {
+        error_type: error.error_type.clone(),
+        span,
+        line: position.line as usize,
+        column: position.column as usize,
+    }
+}
+
+#[cfg(test)]
+mod test_incremental {
+    use super::*;
+    use crate::lexer::{tokenize, tokenize_with_options};
+
+    /// Checks that re-lexing `source` for `edit` gives the same result as
+    /// lexing the edited text from scratch.
+    fn assert_relexes(source: &str, edit: &TextEdit, options: &LexerOptions) {
+        let (old, old_errors) = tokenize_with_options(source, options);
+        let new_source = edit.apply(source);
+        let incremental = relex(&old, &old_errors, edit, &new_source, options);
+        assert_eq!(incremental, tokenize_with_options(&new_source, options), "editing {:?} with {:?}", source, edit);
+    }
+
+    #[test]
+    fn relexes_an_edited_identifier() {
+        let source = "let value = 1\nlet other = value\n";
+        assert_relexes(source, &TextEdit::new(4..9, "renamed"), &LexerOptions::default());
+        assert_relexes(source, &TextEdit::new(9..9, "_x"), &LexerOptions::default());
+    }
+
+    #[test]
+    fn relexes_edits_that_merge_or_split_tokens() {
+        let source = "a = 1 .5 + b";
+        assert_relexes(source, &TextEdit::new(5..6, ""), &LexerOptions::default());
+        assert_relexes(source, &TextEdit::new(1..2, ""), &LexerOptions::default());
+        assert_relexes("a=>b", &TextEdit::new(2..2, " "), &LexerOptions::default());
+    }
+
+    #[test]
+    fn relexes_edits_that_open_a_string_or_comment() {
+        let source = "f(a)\ng(b)\n'c ${d}'\n";
+        assert_relexes(source, &TextEdit::new(2..2, "'"), &LexerOptions::default());
+        assert_relexes(source, &TextEdit::new(5..5, "/*"), &LexerOptions::default());
+        assert_relexes(source, &TextEdit::new(14..15, "}"), &LexerOptions::default());
+    }
+
+    #[test]
+    fn relexes_edits_that_change_newline_termination() {
+        let source = "let x = a\nb\n(c\nd)\n";
+        assert_relexes(source, &TextEdit::new(8..9, "+"), &LexerOptions::default());
+        assert_relexes(source, &TextEdit::new(12..13, ""), &LexerOptions::default());
+    }
+
+    #[test]
+    fn relexes_edits_to_a_fixity_declaration() {
+        let source = "infixl 6 <+> = add\na <+> b\n";
+        assert_relexes(source, &TextEdit::new(5..6, "x"), &LexerOptions::default());
+        assert_relexes(source, &TextEdit::new(7..8, "x"), &LexerOptions::default());
+        assert_relexes("infixl 6\n<+> = add\n", &TextEdit::new(8..8, " 7"), &LexerOptions::default());
+        assert_relexes("infixl 6 <+>= add\n", &TextEdit::new(13..14, ""), &LexerOptions::default());
+    }
+
+    #[test]
+    fn reuses_tokens_after_the_edit() {
+        let source = "let a = 1\nlet b = 2\nlet c = 3\n";
+        let (old, old_errors) = tokenize(source);
+        let edit = TextEdit::new(8..9, "10");
+        let new_source = edit.apply(source);
+        let (tokens, _) = relex(&old, &old_errors, &edit, &new_source, &LexerOptions::default());
+        let last = tokens.last().unwrap();
+        assert_eq!((last.text, last.span.start, last.line), ("\n", 30, 3));
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn matches_a_full_relex_after_any_edit(
+            source in "[a-c0-9 \n/*'${}()=.+;]{0,40}",
+            start in 0usize..41,
+            length in 0usize..6,
+            new_text in "[a-c0-9 \n/*'${}()=.+;]{0,6}",
+            lossless in proptest::bool::ANY,
+        ) {
+            let start = start.min(source.len());
+            let end = (start + length).min(source.len());
+            let edit = TextEdit::new(start as u32..end as u32, new_text);
+            let options = LexerOptions { lossless, ..LexerOptions::default() };
+            let (old, old_errors) = tokenize_with_options(&source, &options);
+            let new_source = edit.apply(&source);
+            let incremental = relex(&old, &old_errors, &edit, &new_source, &options);
+            proptest::prop_assert_eq!(incremental, tokenize_with_options(&new_source, &options));
+        }
+    }
+}
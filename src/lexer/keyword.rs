@@ -0,0 +1,60 @@
+use std::fmt;
+
+/// Words reserved by the language. They are lexed as `TokenKind::Keyword`
+/// and can never be used as identifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    Let,
+    If,
+    Else,
+    True,
+    False,
+    Match,
+    Type,
+    Import,
+    Infix,
+    Infixl,
+    Infixr,
+}
+
+impl Keyword {
+    pub const ALL: [Keyword; 11] = [
+        Keyword::Let,
+        Keyword::If,
+        Keyword::Else,
+        Keyword::True,
+        Keyword::False,
+        Keyword::Match,
+        Keyword::Type,
+        Keyword::Import,
+        Keyword::Infix,
+        Keyword::Infixl,
+        Keyword::Infixr,
+    ];
+
+    pub fn lookup(word: &str) -> Option<Keyword> {
+        Keyword::ALL.iter().copied().find(|keyword| keyword.as_str() == word)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Keyword::Let => "let",
+            Keyword::If => "if",
+            Keyword::Else => "else",
+            Keyword::True => "true",
+            Keyword::False => "false",
+            Keyword::Match => "match",
+            Keyword::Type => "type",
+            Keyword::Import => "import",
+            Keyword::Infix => "infix",
+            Keyword::Infixl => "infixl",
+            Keyword::Infixr => "infixr",
+        }
+    }
+}
+
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
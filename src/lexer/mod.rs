@@ -0,0 +1,1364 @@
+mod cursor;
+mod incremental;
+mod keyword;
+mod number;
+mod span;
+mod string;
+mod symbol;
+
+pub use incremental::{relex, TextEdit};
+pub use keyword::Keyword;
+pub use number::{split_float, split_integer, FloatSuffix, IntegerSuffix};
+pub use span::{FileId, LineCol, LineIndex, SourceFile, SourceMap, Span};
+pub use symbol::{Interner, Symbol};
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fmt;
+
+use cursor::Cursor;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+use unicode_xid::UnicodeXID;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexerErrorType {
+    UnexpectedCharacter(char),
+    /// A `/*` comment that is never closed; the error points at the `/*`.
+    UnterminatedBlockComment,
+    /// A radix prefix such as `0x` that is not followed by any digits.
+    MissingDigits,
+    /// A digit that is not valid in the literal's radix, as in `0b102`.
+    InvalidDigit { digit: char, radix: u32 },
+    /// Letters after a number that are not one of the type suffixes.
+    InvalidNumberSuffix(String),
+    /// A string that reaches the end of input; the error points at the opening quote.
+    UnterminatedString,
+    /// A `\` followed by a character that is not a known escape.
+    InvalidEscape(char),
+    /// A `\u` escape that is malformed or names something other than a Unicode scalar value.
+    InvalidUnicodeEscape,
+}
+
+impl fmt::Display for LexerErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexerErrorType::UnexpectedCharacter(c) => write!(f, "Unexpected character '{}'", c.escape_default()),
+            LexerErrorType::UnterminatedBlockComment => f.write_str("Unterminated block comment"),
+            LexerErrorType::MissingDigits => f.write_str("Expected digits after the radix prefix"),
+            LexerErrorType::InvalidDigit { digit, radix } => write!(f, "'{}' is not a valid digit in base {}", digit, radix),
+            LexerErrorType::InvalidNumberSuffix(suffix) => write!(f, "Invalid number suffix '{}'", suffix),
+            LexerErrorType::UnterminatedString => f.write_str("Unterminated string"),
+            LexerErrorType::InvalidEscape(c) => write!(f, "Invalid escape '\\{}'", c.escape_default()),
+            LexerErrorType::InvalidUnicodeEscape => f.write_str("Invalid unicode escape"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexerError {
+    pub error_type: LexerErrorType,
+    pub span: Span,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for LexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.error_type)
+    }
+}
+
+impl std::error::Error for LexerError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Integer,
+    Float,
+    Operator,
+    Symbol,
+    Keyword(Keyword),
+    /// A line break that terminates an expression. Other line breaks are
+    /// skipped like any other whitespace; see `Lexer` for the rules.
+    NewLine,
+    StringLiteral,
+    /// Opening quote of a string containing `${...}` holes. It is followed by
+    /// `StringFragment`s and `HoleStart` ... `HoleEnd` runs of ordinary
+    /// tokens, and closed by `InterpolationEnd`.
+    InterpolationStart,
+    StringFragment,
+    HoleStart,
+    HoleEnd,
+    InterpolationEnd,
+    /// Input the lexer could not make sense of. A matching `LexerError`
+    /// explains why.
+    Error,
+    /// The end of input. Only produced in lossless mode, to carry the trivia
+    /// after the last token.
+    Eof,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriviaKind {
+    Whitespace,
+    /// A `\n`, `\r\n` or lone `\r`.
+    NewLine,
+    LineComment,
+    BlockComment,
+}
+
+/// Source text that is not part of any token, kept in lossless mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia<'src> {
+    pub kind: TriviaKind,
+    pub text: &'src str,
+    pub span: Span,
+}
+
+/// A token and where it came from. `span` is the exact byte range of the
+/// token's source text `text`, and `value` is what that text means, with
+/// escapes resolved and identifiers normalized. `line` and `column` locate
+/// its start, with the column counted in UTF-8 bytes.
+///
+/// In lossless mode a token also carries the trivia around it: everything
+/// after it up to and including the end of its line is trailing trivia,
+/// and the rest before the next token is that token's leading trivia.
+///
+/// Both `text` and, unless lexing had to change it, `value` borrow from the
+/// source. Identifiers, string literals and string fragments also carry
+/// their value interned as `symbol`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token<'src> {
+    pub kind: TokenKind,
+    pub text: &'src str,
+    pub value: Cow<'src, str>,
+    pub symbol: Option<Symbol>,
+    pub span: Span,
+    pub column: usize,
+    pub line: usize,
+    pub leading_trivia: Vec<Trivia<'src>>,
+    pub trailing_trivia: Vec<Trivia<'src>>,
+}
+
+impl<'src> Token<'src> {
+    pub fn new(kind: TokenKind, text: &'src str, column: usize, line: usize) -> Self {
+        Token {
+            symbol: interned(&kind, text),
+            kind,
+            text,
+            value: Cow::Borrowed(text),
+            span: Span::default(),
+            column,
+            line,
+            leading_trivia: Vec::new(),
+            trailing_trivia: Vec::new(),
+        }
+    }
+
+    /// Replaces the value with `value`, keeping it borrowed if it is the
+    /// same as the source text.
+    fn with_value(self, value: String) -> Self {
+        let text = self.text;
+        self.with_value_from(value, text)
+    }
+
+    /// Replaces the value with `value`, borrowing `raw` instead if the two
+    /// are the same.
+    fn with_value_from(mut self, value: String, raw: &'src str) -> Self {
+        self.value = match value == raw {
+            true => Cow::Borrowed(raw),
+            false => Cow::Owned(value),
+        };
+        self
+    }
+}
+
+/// The symbol for a token of `kind` with `value`, for the kinds that have one.
+fn interned(kind: &TokenKind, value: &str) -> Option<Symbol> {
+    match kind {
+        TokenKind::Symbol | TokenKind::StringLiteral | TokenKind::StringFragment => Some(Symbol::intern(value)),
+        _ => None,
+    }
+}
+
+/// Reassembles the source text from tokens lexed in lossless mode, in the
+/// order `tokenize` returns them.
+pub fn source_text(tokens: &[Token]) -> String {
+    let mut text = String::new();
+    for token in tokens {
+        token.leading_trivia.iter().for_each(|trivia| text.push_str(trivia.text));
+        text.push_str(token.text);
+        token.trailing_trivia.iter().for_each(|trivia| text.push_str(trivia.text));
+    }
+    text
+}
+
+/// Every built-in operator and punctuation token, longest first.
+const OPERATORS: &[&str] = &[
+    "==", "=>", "!=", "<=", ">=", "&&", "||", "|>", "..",
+    "+", "-", "*", "/", "%", "=", "!", "<", ">",
+    ".", ",", ":", ";", "(", ")", "{", "}", "[", "]",
+];
+
+/// Punctuation is always a token of its own.
+const PUNCTUATION: &[char] = &['(', ')', '{', '}', '[', ']', ',', ';'];
+
+/// Characters that make up operators. A run of them is split into the
+/// longest built-in operators, unless it starts with a declared operator
+/// such as `<+>` or `|>>`, which is one token like `==`.
+fn is_operator_char(c: char) -> bool {
+    matches!(c, '+' | '-' | '*' | '/' | '%' | '=' | '!' | '<' | '>' | '&' | '|' | '^' | '?' | '.' | ':')
+}
+
+/// The run of operator characters at the start of `text`, which starts
+/// with one, stopping short of a comment so that `a +// note` is `+` then a
+/// comment.
+fn operator_run(text: &str) -> &str {
+    let end = text
+        .char_indices()
+        .skip(1)
+        .find(|(index, c)| !is_operator_char(*c) || text[*index..].starts_with("//") || text[*index..].starts_with("/*"))
+        .map_or(text.len(), |(index, _)| index);
+    &text[..end]
+}
+
+/// How to lex a source text. Every extension is off by default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LexerOptions {
+    /// The file the input belongs to, recorded in every token's span.
+    pub file: FileId,
+    /// Accept identifiers made of any XID_Start/XID_Continue characters
+    /// instead of only `[a-zA-Z_][a-zA-Z0-9_]*`. Identifiers are NFC
+    /// normalized so that differently composed spellings compare equal.
+    pub unicode_identifiers: bool,
+    /// Keep whitespace, line breaks and comments as trivia on the tokens
+    /// and end the stream with an `Eof` token, so that `source_text` can
+    /// reproduce the input exactly.
+    pub lossless: bool,
+    /// Operators the module declares, which lex as one token instead of
+    /// being split into built-in operators. The operator in a fixity
+    /// declaration itself always lexes as one token.
+    pub operators: Vec<String>,
+}
+
+impl LexerOptions {
+    /// The longest built-in or declared operator that `run`, a run of
+    /// operator characters, starts with.
+    fn longest_operator<'src>(&self, run: &'src str) -> Option<&'src str> {
+        OPERATORS
+            .iter()
+            .copied()
+            .chain(self.operators.iter().map(String::as_str))
+            .filter(|operator| run.starts_with(operator))
+            .max_by_key(|operator| operator.len())
+            .map(|operator| &run[..operator.len()])
+    }
+
+    /// The operator that `run`, a run of operator characters followed by
+    /// `rest`, declares in a fixity declaration. The `=` after the operator
+    /// can be written right against it, as in `infixl 6 <+>=add`, so a
+    /// trailing `=` with an identifier right after it is left out.
+    fn declared_operator<'src>(&self, run: &'src str, rest: &str) -> &'src str {
+        let identifier_follows = rest.chars().next().is_some_and(|c| self.is_identifier_start(c));
+        match run.strip_suffix('=') {
+            Some(operator) if identifier_follows && !operator.is_empty() => operator,
+            _ => run,
+        }
+    }
+
+    fn is_identifier_start(&self, c: char) -> bool {
+        c == '_' || c.is_ascii_alphabetic() || (self.unicode_identifiers && c.is_xid_start())
+    }
+
+    fn is_identifier_continue(&self, c: char) -> bool {
+        c == '_' || c.is_ascii_alphanumeric() || (self.unicode_identifiers && c.is_xid_continue())
+    }
+}
+
+/// How much of a fixity declaration such as `infixl 6 <+> = combine` the
+/// last tokens are. The operator after the precedence is being declared, so
+/// it lexes as one token whatever characters it is made of.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum Declaring {
+    #[default]
+    Nothing,
+    /// After `infix`, `infixl` or `infixr`.
+    Keyword,
+    /// After the keyword and a precedence.
+    Precedence,
+}
+
+impl Declaring {
+    fn after(self, token: &Token) -> Declaring {
+        match (&token.kind, self) {
+            (TokenKind::Keyword(Keyword::Infix | Keyword::Infixl | Keyword::Infixr), _) => Declaring::Keyword,
+            (TokenKind::Integer, Declaring::Keyword) => Declaring::Precedence,
+            _ => Declaring::Nothing,
+        }
+    }
+}
+
+/// What the lexer is in the middle of when strings and holes nest.
+enum Mode {
+    /// Inside the quotes of a string whose opening quote is at `start`.
+    String { quote: char, start: usize, interpolated: bool },
+    /// Inside a `${...}` hole, with `depth` unclosed `{` of its own.
+    Hole { depth: usize },
+}
+
+/// Updates the stack of brackets, and `$` for holes, open after `token`.
+fn track_brackets(brackets: &mut Vec<char>, token: &Token) {
+    match (&token.kind, token.text) {
+        (TokenKind::Operator, "(" | "[" | "{") => brackets.extend(token.text.chars()),
+        (TokenKind::HoleStart, _) => brackets.push('$'),
+        (TokenKind::Operator, ")" | "]" | "}") | (TokenKind::HoleEnd, _) => {
+            brackets.pop();
+        }
+        _ => {}
+    }
+}
+
+/// Whether `token` can be the last token of an expression.
+fn ends_expression(token: &Token) -> bool {
+    match token.kind {
+        TokenKind::Integer | TokenKind::Float | TokenKind::Symbol => true,
+        TokenKind::StringLiteral | TokenKind::InterpolationEnd => true,
+        TokenKind::Keyword(keyword) => matches!(keyword, Keyword::True | Keyword::False),
+        TokenKind::Operator => matches!(token.text, ")" | "]" | "}"),
+        // the parser takes bad input for an expression of its own
+        TokenKind::Error => true,
+        _ => false,
+    }
+}
+
+/// Lexes a source text lazily, one token at a time. Token text borrows from
+/// the source, and lexing carries on past bad input, yielding an `Err` for
+/// each problem alongside an `Error` token covering the bad input.
+///
+/// A line break becomes a `NewLine` token when the token before it can end
+/// an expression (a literal, an identifier, `true`, `false`, `)`, `]`, `}`
+/// or bad input) and it is not inside `(...)`, `[...]` or a `${...}` hole.
+/// Any other line break is whitespace, so an expression continues onto the
+/// next line after an operator or an open bracket. Blank lines and comments after a
+/// `NewLine` are whitespace too, so terminators never repeat.
+pub struct Lexer<'src> {
+    cursor: Cursor<'src>,
+    lines: LineIndex,
+    /// The 0-based line of the last token or error located.
+    line_hint: usize,
+    options: LexerOptions,
+    modes: Vec<Mode>,
+    /// Tokens and errors lexed but not yet yielded. A token stays here until
+    /// its trailing trivia is known.
+    queue: VecDeque<Result<Token<'src>, LexerError>>,
+    /// Errors recorded by the sub-lexers while lexing the current token.
+    errors: Vec<LexerError>,
+    /// Trivia waiting for the next token, in lossless mode.
+    leading_trivia: Vec<Trivia<'src>>,
+    /// The brackets, and `$` for holes, enclosing the cursor.
+    brackets: Vec<char>,
+    /// Whether the last token can end an expression.
+    ends_expression: bool,
+    declaring: Declaring,
+    finished: bool,
+}
+
+impl<'src> Lexer<'src> {
+    pub fn new(source: &'src str) -> Self {
+        Lexer::with_options(source, &LexerOptions::default())
+    }
+
+    pub fn with_options(source: &'src str, options: &LexerOptions) -> Self {
+        Lexer {
+            cursor: Cursor::new(source, options.file),
+            lines: LineIndex::new(source),
+            line_hint: 0,
+            options: options.clone(),
+            modes: Vec::new(),
+            queue: VecDeque::new(),
+            errors: Vec::new(),
+            leading_trivia: Vec::new(),
+            brackets: Vec::new(),
+            ends_expression: false,
+            declaring: Declaring::Nothing,
+            finished: false,
+        }
+    }
+
+    /// Queues `token` after any errors recorded while lexing it.
+    fn push(&mut self, mut token: Token<'src>) {
+        self.flush_errors();
+        token.symbol = interned(&token.kind, &token.value);
+        if self.options.lossless {
+            token.leading_trivia = std::mem::take(&mut self.leading_trivia);
+        }
+        track_brackets(&mut self.brackets, &token);
+        self.ends_expression = ends_expression(&token);
+        self.declaring = self.declaring.after(&token);
+        self.queue.push_back(Ok(token));
+    }
+
+    /// Whether a line break at the cursor terminates an expression.
+    fn at_terminator(&self) -> bool {
+        self.ends_expression && matches!(self.brackets.last(), None | Some('{'))
+    }
+
+    fn flush_errors(&mut self) {
+        self.queue.extend(self.errors.drain(..).map(Err));
+    }
+
+    /// Records `error` and covers the input from `start` up to the cursor
+    /// with an `Error` token so the token stream still accounts for it.
+    fn recover(&mut self, start: usize, error: LexerError) {
+        self.errors.push(error);
+        self.push(self.cursor.token(TokenKind::Error, start));
+    }
+
+    /// Lexes until at least one token or error is queued, or the input ends.
+    fn advance(&mut self) {
+        if matches!(self.modes.last(), Some(Mode::String { .. })) {
+            self.lex_string_part();
+            return;
+        }
+
+        while let Some(trivia) = self.lex_trivia() {
+            if self.options.lossless {
+                self.leading_trivia.push(trivia);
+            }
+        }
+        let start = self.cursor.offset();
+        let c = match self.cursor.peek() {
+            Some(c) => c,
+            None => {
+                self.finish();
+                return;
+            }
+        };
+        self.lex_token(c, start);
+
+        // trailing trivia only exists between tokens, not inside the quotes,
+        // and a NewLine token already ends its line
+        let in_string = matches!(self.modes.last(), Some(Mode::String { .. }));
+        if self.options.lossless && !in_string && !matches!(c, '\n' | '\r') {
+            while let Some(trivia) = self.lex_trivia() {
+                let ends_line = trivia.kind == TriviaKind::NewLine;
+                if let Some(Ok(token)) = self.queue.iter_mut().rev().find(|item| item.is_ok()) {
+                    token.trailing_trivia.push(trivia);
+                }
+                if ends_line {
+                    break;
+                }
+            }
+        }
+        self.flush_errors();
+    }
+
+    /// Lexes one piece of whitespace, line break or comment, if the cursor
+    /// is at one.
+    fn lex_trivia(&mut self) -> Option<Trivia<'src>> {
+        let start = self.cursor.offset();
+        let kind = match (self.cursor.peek()?, self.cursor.peek_second()) {
+            (' ' | '\t', _) => {
+                while self.cursor.peek().is_some_and(|c| c == ' ' || c == '\t') {
+                    self.cursor.next();
+                }
+                TriviaKind::Whitespace
+            }
+            (c @ ('\n' | '\r'), next) if !self.at_terminator() => {
+                self.cursor.next();
+                if c == '\r' && next == Some('\n') {
+                    self.cursor.next();
+                }
+                TriviaKind::NewLine
+            }
+            ('/', Some('/')) => {
+                while self.cursor.peek().is_some_and(|c| c != '\n' && c != '\r') {
+                    self.cursor.next();
+                }
+                TriviaKind::LineComment
+            }
+            ('/', Some('*')) => {
+                self.cursor.next();
+                self.cursor.next();
+                // block comments nest, so track how many are still open
+                let mut depth = 1;
+                while depth > 0 {
+                    match self.cursor.next() {
+                        Some('/') if self.cursor.peek() == Some('*') => {
+                            self.cursor.next();
+                            depth += 1;
+                        }
+                        Some('*') if self.cursor.peek() == Some('/') => {
+                            self.cursor.next();
+                            depth -= 1;
+                        }
+                        Some(_) => {}
+                        None => {
+                            let opening = self.cursor.span(start, start + 2);
+                            self.errors.push(self.cursor.error_at(LexerErrorType::UnterminatedBlockComment, opening));
+                            break;
+                        }
+                    }
+                }
+                TriviaKind::BlockComment
+            }
+            _ => {
+                return None;
+            }
+        };
+        Some(self.cursor.trivia(kind, start))
+    }
+
+    /// Lexes the next fragment of the string the lexer is inside, and the
+    /// delimiter that ends it.
+    fn lex_string_part(&mut self) {
+        let (quote, start, interpolated) = match self.modes.last() {
+            Some(Mode::String { quote, start, interpolated }) => (*quote, *start, *interpolated),
+            _ => return,
+        };
+        let fragment_start = self.cursor.offset();
+        let (value, end) = match string::lex_fragment(&mut self.cursor, quote, start, &mut self.errors) {
+            Ok(fragment) => fragment,
+            Err(error) => {
+                let error_start = if interpolated { fragment_start } else { start };
+                self.recover(error_start, error);
+                self.modes.clear();
+                return;
+            }
+        };
+        if end == string::FragmentEnd::Closed && !interpolated {
+            let token = self.cursor.token(TokenKind::StringLiteral, start);
+            let contents = &token.text[1..token.text.len() - 1];
+            self.push(token.with_value_from(value, contents));
+            self.modes.pop();
+            return;
+        }
+        if !interpolated {
+            self.push(self.cursor.token_between(TokenKind::InterpolationStart, start, fragment_start));
+            if let Some(Mode::String { interpolated, .. }) = self.modes.last_mut() {
+                *interpolated = true;
+            }
+        }
+        let delimiter_start = match end {
+            string::FragmentEnd::Closed => self.cursor.offset() - 1,
+            string::FragmentEnd::Hole => self.cursor.offset() - 2,
+        };
+        if !value.is_empty() {
+            let token = self.cursor.token_between(TokenKind::StringFragment, fragment_start, delimiter_start);
+            self.push(token.with_value(value));
+        }
+        match end {
+            string::FragmentEnd::Closed => {
+                self.push(self.cursor.token(TokenKind::InterpolationEnd, delimiter_start));
+                self.modes.pop();
+            }
+            string::FragmentEnd::Hole => {
+                self.push(self.cursor.token(TokenKind::HoleStart, delimiter_start));
+                self.modes.push(Mode::Hole { depth: 0 });
+            }
+        }
+    }
+
+    /// Lexes the token starting with `c` at `start`.
+    fn lex_token(&mut self, c: char, start: usize) {
+        let cursor = &mut self.cursor;
+        match c {
+            '0'..='9' => {
+                match number::lex_number(cursor) {
+                    Ok(kind) => {
+                        self.push(self.cursor.token(kind, start));
+                    }
+                    Err(error) => {
+                        // skip the rest of the literal so it is not lexed again as a new token
+                        while cursor.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+                            cursor.next();
+                        }
+                        self.recover(start, error);
+                    }
+                }
+            }
+            '}' if matches!(self.modes.last(), Some(Mode::Hole { depth: 0 })) => {
+                cursor.next();
+                self.push(self.cursor.token(TokenKind::HoleEnd, start));
+                self.modes.pop();
+            }
+            c if PUNCTUATION.contains(&c) => {
+                if let Some(Mode::Hole { depth }) = self.modes.last_mut() {
+                    match c {
+                        '{' => *depth += 1,
+                        '}' => *depth -= 1,
+                        _ => {}
+                    }
+                }
+                cursor.next();
+                self.push(self.cursor.token(TokenKind::Operator, start));
+            }
+            c if is_operator_char(c) => {
+                let run = operator_run(cursor.rest());
+                let operator = match self.declaring {
+                    Declaring::Precedence => Some(self.options.declared_operator(run, &cursor.rest()[run.len()..])),
+                    _ => self.options.longest_operator(run),
+                };
+                match operator {
+                    Some(operator) => {
+                        self.cursor.seek(start + operator.len());
+                        self.push(self.cursor.token(TokenKind::Operator, start));
+                    }
+                    None => {
+                        self.cursor.next();
+                        let error = self.cursor.error(LexerErrorType::UnexpectedCharacter(c), start);
+                        self.recover(start, error);
+                    }
+                }
+            }
+            'r' if matches!(cursor.peek_second(), Some('\'' | '"' | '#')) => {
+                match string::lex_raw(cursor) {
+                    Ok(value) => {
+                        let token = self.cursor.token(TokenKind::StringLiteral, start);
+                        let contents = token.text.trim_start_matches('r').trim_matches('#');
+                        let contents = &contents[1..contents.len() - 1];
+                        self.push(token.with_value_from(value, contents));
+                    }
+                    Err(error) => self.recover(start, error),
+                }
+            }
+            c if self.options.is_identifier_start(c) => {
+                while cursor.peek().is_some_and(|c| self.options.is_identifier_continue(c)) {
+                    cursor.next();
+                }
+                let mut token = cursor.token(TokenKind::Symbol, start);
+                if self.options.unicode_identifiers && !is_nfc(token.text) {
+                    let normalized = token.text.nfc().collect();
+                    token = token.with_value(normalized);
+                }
+                if let Some(keyword) = Keyword::lookup(&token.value) {
+                    token.kind = TokenKind::Keyword(keyword);
+                }
+                self.push(token);
+            }
+            '"' | '\'' if cursor.rest().chars().take(3).filter(|next| *next == c).count() == 3 => {
+                match string::lex_triple_quoted(cursor, &mut self.errors) {
+                    Ok(value) => {
+                        let token = self.cursor.token(TokenKind::StringLiteral, start);
+                        self.push(token.with_value(value));
+                    }
+                    Err(error) => self.recover(start, error),
+                }
+            }
+            '"' | '\'' => {
+                cursor.next();
+                self.modes.push(Mode::String { quote: c, start, interpolated: false });
+            }
+            '\n' | '\r' => {
+                cursor.next();
+                if c == '\r' && cursor.peek() == Some('\n') {
+                    cursor.next();
+                }
+                self.push(self.cursor.token(TokenKind::NewLine, start));
+            }
+            _ => {
+                cursor.next();
+                let error = cursor.error(LexerErrorType::UnexpectedCharacter(c), start);
+                self.recover(start, error);
+            }
+        }
+    }
+
+    /// Reports a string left open at the end of input and, in lossless
+    /// mode, queues the `Eof` token.
+    fn finish(&mut self) {
+        // a hole can only be left open at EOF inside an interpolated string
+        let open_string = self.modes.iter().rev().find_map(|mode| match mode {
+            Mode::String { start, .. } => Some(*start),
+            Mode::Hole { .. } => None,
+        });
+        if let Some(start) = open_string {
+            let opening = self.cursor.span(start, start + 1);
+            self.errors.push(self.cursor.error_at(LexerErrorType::UnterminatedString, opening));
+        }
+        self.modes.clear();
+        if self.options.lossless {
+            self.push(self.cursor.token(TokenKind::Eof, self.cursor.offset()));
+        }
+        self.flush_errors();
+        self.finished = true;
+    }
+
+    /// The source text being lexed.
+    pub fn source(&self) -> &'src str {
+        self.cursor.source()
+    }
+
+    pub fn options(&self) -> &LexerOptions {
+        &self.options
+    }
+
+    /// An `Eof` token at the end of the input, located like any other
+    /// token, for a parser to report a missing token at when the stream is
+    /// not lossless and so has none of its own.
+    pub fn eof(&mut self) -> Token<'src> {
+        let end = self.cursor.offset() + self.cursor.rest().len();
+        let mut token = self.cursor.token_between(TokenKind::Eof, end, end);
+        (token.line, token.column) = self.position(token.span.start);
+        token
+    }
+
+    /// Fills in the line and column of a token or error from its span.
+    fn locate(&mut self, item: Result<Token<'src>, LexerError>) -> Result<Token<'src>, LexerError> {
+        match item {
+            Ok(mut token) => {
+                (token.line, token.column) = self.position(token.span.start);
+                Ok(token)
+            }
+            Err(mut error) => {
+                (error.line, error.column) = self.position(error.span.start);
+                Err(error)
+            }
+        }
+    }
+
+    /// The 1-based line and column of `offset`.
+    fn position(&mut self, offset: u32) -> (usize, usize) {
+        let position = self.lines.line_col_from(offset, &mut self.line_hint);
+        (position.line as usize, position.column as usize)
+    }
+}
+
+impl<'src> Iterator for Lexer<'src> {
+    type Item = Result<Token<'src>, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.queue.is_empty() && !self.finished {
+            self.advance();
+        }
+        let item = self.queue.pop_front()?;
+        Some(self.locate(item))
+    }
+}
+
+/// Lexes the whole input, carrying on past bad input so that every problem
+/// in the file is reported at once. The tokens are returned in source order.
+pub fn tokenize(input: &str) -> (Vec<Token<'_>>, Vec<LexerError>) {
+    tokenize_with_options(input, &LexerOptions::default())
+}
+
+pub fn tokenize_with_options<'src>(input: &'src str, options: &LexerOptions) -> (Vec<Token<'src>>, Vec<LexerError>) {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for item in Lexer::with_options(input, options) {
+        match item {
+            Ok(token) => tokens.push(token),
+            Err(error) => errors.push(error),
+        }
+    }
+    (tokens, errors)
+}
+
+#[cfg(test)]
+mod test_lexer {
+    use super::*;
+
+    /// Lexes `input`, failing with the first error if there were any.
+    fn lex_with<'a>(input: &'a str, options: &LexerOptions) -> Result<Vec<Token<'a>>, LexerError> {
+        let (tokens, mut errors) = tokenize_with_options(input, options);
+        match errors.is_empty() {
+            true => Ok(tokens),
+            false => Err(errors.remove(0)),
+        }
+    }
+
+    fn lex(input: &str) -> Result<Vec<Token<'_>>, LexerError> {
+        lex_with(input, &LexerOptions::default())
+    }
+
+    fn location(result: Result<Vec<Token>, LexerError>) -> (LexerErrorType, usize, usize) {
+        let error = result.unwrap_err();
+        (error.error_type, error.line, error.column)
+    }
+
+    fn values(input: &str) -> Vec<String> {
+        lex(input)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.value.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn skips_line_comments() {
+        assert_eq!(values("// a comment\nlet x = 1 // trailing"), vec!["let", "x", "=", "1"]);
+    }
+
+    #[test]
+    fn skips_block_comments() {
+        assert_eq!(values("let /* inline */ x = /* multi\nline */ 1"), vec!["let", "x", "=", "1"]);
+    }
+
+    #[test]
+    fn skips_nested_block_comments() {
+        assert_eq!(values("1 /* outer /* inner */ still outer */ 2"), vec!["1", "2"]);
+    }
+
+    #[test]
+    fn keeps_division_operator() {
+        assert_eq!(values("a / b"), vec!["a", "/", "b"]);
+    }
+
+    #[test]
+    fn lexes_every_operator() {
+        let input = OPERATORS.join(" ");
+        assert_eq!(values(&input), OPERATORS.to_vec());
+    }
+
+    #[test]
+    fn lexes_longest_operator_first() {
+        assert_eq!(values("a<=b=>c==d|>e"), vec!["a", "<=", "b", "=>", "c", "==", "d", "|>", "e"]);
+        assert_eq!(values("!!x"), vec!["!", "!", "x"]);
+        assert_eq!(values("a=>=b"), vec!["a", "=>", "=", "b"]);
+    }
+
+    #[test]
+    fn splits_prefix_operators_off_binary_ones() {
+        assert_eq!(values("a*-b"), vec!["a", "*", "-", "b"]);
+        assert_eq!(values("(x)=>!x"), vec!["(", "x", ")", "=>", "!", "x"]);
+        assert_eq!(values("xs[1..-1]"), vec!["xs", "[", "1", "..", "-", "1", "]"]);
+    }
+
+    #[test]
+    fn lexes_declared_operators_as_one_token() {
+        let options = LexerOptions { operators: vec!["<+>".to_string(), "|>>".to_string()], ..LexerOptions::default() };
+        let declared: Vec<String> = lex_with("a <+> b |>> c<+>-d", &options).unwrap().into_iter().map(|token| token.value.into_owned()).collect();
+        assert_eq!(declared, vec!["a", "<+>", "b", "|>>", "c", "<+>", "-", "d"]);
+        assert_eq!(values("a <+> b"), vec!["a", "<", "+", ">", "b"]);
+    }
+
+    #[test]
+    fn lexes_the_operator_in_a_fixity_declaration_as_one_token() {
+        assert_eq!(values("infixr 5 |>> = pipe"), vec!["infixr", "5", "|>>", "=", "pipe"]);
+        assert_eq!(values("infix 4 <&> = both"), vec!["infix", "4", "<&>", "=", "both"]);
+    }
+
+    #[test]
+    fn splits_the_equals_sign_off_a_declared_operator_written_against_it() {
+        assert_eq!(values("infixl 6 <+>=add"), vec!["infixl", "6", "<+>", "=", "add"]);
+        assert_eq!(values("infixr 1 += = assign"), vec!["infixr", "1", "+=", "=", "assign"]);
+    }
+
+    #[test]
+    fn never_joins_punctuation_into_operators() {
+        assert_eq!(values("f(-x);"), vec!["f", "(", "-", "x", ")", ";"]);
+        assert_eq!(values("{=}"), vec!["{", "=", "}"]);
+    }
+
+    #[test]
+    fn ends_operators_before_comments() {
+        assert_eq!(values("a +// note
+b"), vec!["a", "+", "b"]);
+        assert_eq!(values("a </* note */ b"), vec!["a", "<", "b"]);
+    }
+
+    #[test]
+    fn lexes_call_arguments() {
+        assert_eq!(values("add(1, 2)"), vec!["add", "(", "1", ",", "2", ")"]);
+    }
+
+    #[test]
+    fn lexes_keywords() {
+        let kinds: Vec<TokenKind> = lex("let x = if true else lets")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect();
+        assert_eq!(kinds, vec![
+            TokenKind::Keyword(Keyword::Let),
+            TokenKind::Symbol,
+            TokenKind::Operator,
+            TokenKind::Keyword(Keyword::If),
+            TokenKind::Keyword(Keyword::True),
+            TokenKind::Keyword(Keyword::Else),
+            TokenKind::Symbol,
+        ]);
+    }
+
+    #[test]
+    fn lexes_identifiers_with_underscores_and_digits() {
+        assert_eq!(values("my_var x1 _unused __a_1_"), vec!["my_var", "x1", "_unused", "__a_1_"]);
+    }
+
+    #[test]
+    fn does_not_start_identifiers_with_digits() {
+        let result = lex("1x");
+        assert_eq!(result.unwrap_err().error_type, LexerErrorType::InvalidNumberSuffix("x".to_string()));
+    }
+
+    fn kinds_and_values(input: &str) -> Vec<(TokenKind, String)> {
+        lex(input)
+            .unwrap()
+            .into_iter()
+            .map(|token| (token.kind, token.value.into_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn lexes_integer_literals() {
+        assert_eq!(kinds_and_values("0 42 1_000_000 0xff 0o17 0b1010_1010 7u8 0x7fi64"), vec![
+            (TokenKind::Integer, "0".to_string()),
+            (TokenKind::Integer, "42".to_string()),
+            (TokenKind::Integer, "1_000_000".to_string()),
+            (TokenKind::Integer, "0xff".to_string()),
+            (TokenKind::Integer, "0o17".to_string()),
+            (TokenKind::Integer, "0b1010_1010".to_string()),
+            (TokenKind::Integer, "7u8".to_string()),
+            (TokenKind::Integer, "0x7fi64".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn lexes_float_literals() {
+        assert_eq!(kinds_and_values("1.5 1e10 2.5E-3 6.02e+23 1_000.000_1 3f32 1.0f64"), vec![
+            (TokenKind::Float, "1.5".to_string()),
+            (TokenKind::Float, "1e10".to_string()),
+            (TokenKind::Float, "2.5E-3".to_string()),
+            (TokenKind::Float, "6.02e+23".to_string()),
+            (TokenKind::Float, "1_000.000_1".to_string()),
+            (TokenKind::Float, "3f32".to_string()),
+            (TokenKind::Float, "1.0f64".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn does_not_treat_a_trailing_dot_as_a_fraction() {
+        assert_eq!(values("1.x"), vec!["1", ".", "x"]);
+    }
+
+    #[test]
+    fn returns_errors_for_malformed_numbers() {
+        let error_type = |input: &str| lex(input).unwrap_err().error_type;
+        assert_eq!(error_type("0x"), LexerErrorType::MissingDigits);
+        assert_eq!(error_type("0b102"), LexerErrorType::InvalidDigit { digit: '2', radix: 2 });
+        assert_eq!(error_type("0o8"), LexerErrorType::InvalidDigit { digit: '8', radix: 8 });
+        assert_eq!(error_type("1.5u8"), LexerErrorType::InvalidNumberSuffix("u8".to_string()));
+        assert_eq!(error_type("0b1f32"), LexerErrorType::InvalidNumberSuffix("f32".to_string()));
+        assert_eq!(error_type("12abc"), LexerErrorType::InvalidNumberSuffix("abc".to_string()));
+    }
+
+    #[test]
+    fn returns_error_for_unicode_identifiers_by_default() {
+        let result = lex("größe");
+        assert_eq!(location(result), (LexerErrorType::UnexpectedCharacter('ö'), 1, 3));
+    }
+
+    #[test]
+    fn lexes_unicode_identifiers_when_enabled() {
+        let options = LexerOptions { unicode_identifiers: true, ..LexerOptions::default() };
+        let tokens = lex_with("größe λ_1 名前", &options).unwrap();
+        let values: Vec<String> = tokens.into_iter().map(|token| token.value.into_owned()).collect();
+        assert_eq!(values, vec!["größe", "λ_1", "名前"]);
+    }
+
+    #[test]
+    fn normalizes_unicode_identifiers_to_nfc() {
+        let options = LexerOptions { unicode_identifiers: true, ..LexerOptions::default() };
+        // "e" followed by a combining acute accent composes to a single "é"
+        let tokens = lex_with("cafe\u{301}", &options).unwrap();
+        assert_eq!(tokens[0].value, "caf\u{e9}");
+        assert_eq!(tokens[0].kind, TokenKind::Symbol);
+    }
+
+    #[test]
+    fn lexes_strings_with_either_quote() {
+        assert_eq!(kinds_and_values("'single' \"double\" 'it\"s' \"it's\""), vec![
+            (TokenKind::StringLiteral, "single".to_string()),
+            (TokenKind::StringLiteral, "double".to_string()),
+            (TokenKind::StringLiteral, "it\"s".to_string()),
+            (TokenKind::StringLiteral, "it's".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn resolves_string_escapes() {
+        assert_eq!(values(r#"'a\nb\tc\\d\'e\"f\0\r'"#), vec!["a\nb\tc\\d'e\"f\0\r"]);
+        assert_eq!(values(r"'\u{1F600} \u{e9}'"), vec!["\u{1F600} \u{e9}"]);
+    }
+
+    #[test]
+    fn lexes_interpolated_strings_into_parts() {
+        assert_eq!(kinds_and_values("'a ${x + 1} b'"), vec![
+            (TokenKind::InterpolationStart, "'".to_string()),
+            (TokenKind::StringFragment, "a ".to_string()),
+            (TokenKind::HoleStart, "${".to_string()),
+            (TokenKind::Symbol, "x".to_string()),
+            (TokenKind::Operator, "+".to_string()),
+            (TokenKind::Integer, "1".to_string()),
+            (TokenKind::HoleEnd, "}".to_string()),
+            (TokenKind::StringFragment, " b".to_string()),
+            (TokenKind::InterpolationEnd, "'".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn lexes_nested_braces_and_strings_in_holes() {
+        assert_eq!(kinds_and_values("\"${ {'}'} }\""), vec![
+            (TokenKind::InterpolationStart, "\"".to_string()),
+            (TokenKind::HoleStart, "${".to_string()),
+            (TokenKind::Operator, "{".to_string()),
+            (TokenKind::StringLiteral, "}".to_string()),
+            (TokenKind::Operator, "}".to_string()),
+            (TokenKind::HoleEnd, "}".to_string()),
+            (TokenKind::InterpolationEnd, "\"".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn does_not_interpolate_escaped_dollar() {
+        assert_eq!(kinds_and_values(r"'\${x} $y'"), vec![(TokenKind::StringLiteral, "${x} $y".to_string())]);
+    }
+
+    #[test]
+    fn returns_error_for_unterminated_hole() {
+        let result = lex("  'a ${b");
+        assert_eq!(location(result), (LexerErrorType::UnterminatedString, 1, 3));
+    }
+
+    #[test]
+    fn lexes_triple_quoted_strings_without_common_indentation() {
+        let input = "let sql = '''\n    SELECT *\n      FROM t\n\n    WHERE x = 'y'\n    '''\nnext";
+        let tokens: Vec<Token> = lex(input).unwrap();
+        assert_eq!(tokens[3].kind, TokenKind::StringLiteral);
+        assert_eq!(tokens[3].value, "SELECT *\n  FROM t\n\nWHERE x = 'y'");
+        assert_eq!(tokens[4].kind, TokenKind::NewLine);
+        assert_eq!((tokens[5].value.as_ref(), tokens[5].line), ("next", 7));
+    }
+
+    #[test]
+    fn lexes_triple_quoted_strings_on_one_line() {
+        assert_eq!(values(r#"'''it's "quoted"''' """a\tb""""#), vec!["it's \"quoted\"", "a\tb"]);
+        assert_eq!(values("'''${x}'''"), vec!["${x}"]);
+        assert_eq!(values("'' x ''"), vec!["", "x", ""]);
+    }
+
+    #[test]
+    fn returns_error_for_unterminated_triple_quoted_string() {
+        let result = lex("'''abc\n''");
+        assert_eq!(location(result), (LexerErrorType::UnterminatedString, 1, 1));
+    }
+
+    #[test]
+    fn lexes_raw_strings_without_escapes() {
+        assert_eq!(values(r#"r'C:\new\${x}' r"\d+""#), vec![r"C:\new\${x}", r"\d+"]);
+        assert_eq!(values(r###"r#'it's'# r##"a"#b"## r"###), vec!["it's", "a\"#b", "r"]);
+    }
+
+    #[test]
+    fn tracks_lines_across_raw_strings() {
+        let tokens: Vec<Token> = lex("r'a\nb\nc' x").unwrap();
+        assert_eq!(tokens[0].value, "a\nb\nc");
+        assert_eq!((tokens[1].value.as_ref(), tokens[1].line), ("x", 3));
+    }
+
+    #[test]
+    fn returns_error_for_unterminated_raw_string() {
+        let result = lex("  r#'abc'");
+        assert_eq!(location(result), (LexerErrorType::UnterminatedString, 1, 3));
+    }
+
+    #[test]
+    fn returns_error_for_unterminated_string() {
+        let result = lex("  'abc\ndef");
+        assert_eq!(location(result), (LexerErrorType::UnterminatedString, 1, 3));
+        let result = lex("'abc\\");
+        assert_eq!(result.unwrap_err().error_type, LexerErrorType::UnterminatedString);
+    }
+
+    #[test]
+    fn returns_error_pointing_at_invalid_escape() {
+        let result = lex(r"'ab\q'");
+        assert_eq!(location(result), (LexerErrorType::InvalidEscape('q'), 1, 4));
+    }
+
+    #[test]
+    fn returns_error_for_invalid_unicode_escapes() {
+        let error = |input: &str| lex(input).unwrap_err();
+        assert_eq!(location(Err(error(r"'\u0041'"))), (LexerErrorType::InvalidUnicodeEscape, 1, 2));
+        assert_eq!(error(r"'\u{}'").error_type, LexerErrorType::InvalidUnicodeEscape);
+        assert_eq!(error(r"'\u{D800}'").error_type, LexerErrorType::InvalidUnicodeEscape);
+        assert_eq!(error(r"'\u{110000}'").error_type, LexerErrorType::InvalidUnicodeEscape);
+        assert_eq!(error(r"'\u{1234567}'").error_type, LexerErrorType::InvalidUnicodeEscape);
+        assert_eq!(error(r"'\u{zz}'").error_type, LexerErrorType::InvalidUnicodeEscape);
+    }
+
+    fn spans(input: &str) -> Vec<(String, u32, u32, usize, usize)> {
+        lex(input)
+            .unwrap()
+            .into_iter()
+            .map(|token| (token.value.into_owned(), token.span.start, token.span.end, token.line, token.column))
+            .collect()
+    }
+
+    #[test]
+    fn records_spans_and_positions_for_every_token() {
+        assert_eq!(spans("let x1 = 42\n  f('hi', 1.5)"), vec![
+            ("let".to_string(), 0, 3, 1, 1),
+            ("x1".to_string(), 4, 6, 1, 5),
+            ("=".to_string(), 7, 8, 1, 8),
+            ("42".to_string(), 9, 11, 1, 10),
+            ("\n".to_string(), 11, 12, 1, 12),
+            ("f".to_string(), 14, 15, 2, 3),
+            ("(".to_string(), 15, 16, 2, 4),
+            ("hi".to_string(), 16, 20, 2, 5),
+            (",".to_string(), 20, 21, 2, 9),
+            ("1.5".to_string(), 22, 25, 2, 11),
+            (")".to_string(), 25, 26, 2, 14),
+        ]);
+    }
+
+    #[test]
+    fn records_positions_across_tabs_crlf_and_multi_byte_characters() {
+        assert_eq!(spans("'é'\tx\r\n\ty /* ü\r\n */ z"), vec![
+            ("é".to_string(), 0, 4, 1, 1),
+            ("x".to_string(), 5, 6, 1, 6),
+            ("\r\n".to_string(), 6, 8, 1, 7),
+            ("y".to_string(), 9, 10, 2, 2),
+            ("z".to_string(), 22, 23, 3, 5),
+        ]);
+    }
+
+    #[test]
+    fn records_spans_of_string_parts() {
+        assert_eq!(spans("'a${b}'"), vec![
+            ("'".to_string(), 0, 1, 1, 1),
+            ("a".to_string(), 1, 2, 1, 2),
+            ("${".to_string(), 2, 4, 1, 3),
+            ("b".to_string(), 4, 5, 1, 5),
+            ("}".to_string(), 5, 6, 1, 6),
+            ("'".to_string(), 6, 7, 1, 7),
+        ]);
+    }
+
+    #[test]
+    fn records_the_file_in_spans() {
+        let options = LexerOptions { file: FileId(3), ..LexerOptions::default() };
+        let tokens = lex_with("x", &options).unwrap();
+        assert_eq!(tokens[0].span, Span::new(FileId(3), 0, 1));
+    }
+
+    #[test]
+    fn returns_error_spans() {
+        let error = lex("'ab\\q'").unwrap_err();
+        assert_eq!(error.span, Span::new(FileId(0), 3, 5));
+        let error = lex("x = 0b12").unwrap_err();
+        assert_eq!(error.span, Span::new(FileId(0), 7, 8));
+    }
+
+    #[test]
+    fn reports_every_unexpected_character() {
+        let (tokens, errors) = tokenize("a # b @ c\n  ~");
+        let kinds: Vec<(TokenKind, String)> = tokens.into_iter().map(|token| (token.kind, token.value.into_owned())).collect();
+        assert_eq!(kinds, vec![
+            (TokenKind::Symbol, "a".to_string()),
+            (TokenKind::Error, "#".to_string()),
+            (TokenKind::Symbol, "b".to_string()),
+            (TokenKind::Error, "@".to_string()),
+            (TokenKind::Symbol, "c".to_string()),
+            (TokenKind::NewLine, "\n".to_string()),
+            (TokenKind::Error, "~".to_string()),
+        ]);
+        let locations: Vec<(LexerErrorType, usize, usize)> = errors.into_iter().map(|error| (error.error_type, error.line, error.column)).collect();
+        assert_eq!(locations, vec![
+            (LexerErrorType::UnexpectedCharacter('#'), 1, 3),
+            (LexerErrorType::UnexpectedCharacter('@'), 1, 7),
+            (LexerErrorType::UnexpectedCharacter('~'), 2, 3),
+        ]);
+    }
+
+    #[test]
+    fn recovers_after_malformed_numbers() {
+        let (tokens, errors) = tokenize("0b102x + 0x");
+        let kinds: Vec<(TokenKind, String)> = tokens.into_iter().map(|token| (token.kind, token.value.into_owned())).collect();
+        assert_eq!(kinds, vec![
+            (TokenKind::Error, "0b102x".to_string()),
+            (TokenKind::Operator, "+".to_string()),
+            (TokenKind::Error, "0x".to_string()),
+        ]);
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn keeps_strings_with_invalid_escapes() {
+        let (tokens, errors) = tokenize(r"'a\qb\u{zz}' x");
+        let kinds: Vec<(TokenKind, String)> = tokens.into_iter().map(|token| (token.kind, token.value.into_owned())).collect();
+        assert_eq!(kinds, vec![
+            (TokenKind::StringLiteral, r"a\qb\u{zz}".to_string()),
+            (TokenKind::Symbol, "x".to_string()),
+        ]);
+        let error_types: Vec<LexerErrorType> = errors.into_iter().map(|error| error.error_type).collect();
+        assert_eq!(error_types, vec![LexerErrorType::InvalidEscape('q'), LexerErrorType::InvalidUnicodeEscape]);
+    }
+
+    #[test]
+    fn covers_unterminated_strings_with_an_error_token() {
+        let (tokens, errors) = tokenize("x 'abc");
+        assert_eq!(tokens[1].kind, TokenKind::Error);
+        assert_eq!(tokens[1].value, "'abc");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn returns_error_for_partial_operator() {
+        let result = lex("  & b");
+        assert_eq!(location(result), (LexerErrorType::UnexpectedCharacter('&'), 1, 3));
+    }
+
+    #[test]
+    fn returns_error_for_unterminated_block_comment() {
+        let result = lex("x\n  /* open /* nested */");
+        assert_eq!(location(result), (LexerErrorType::UnterminatedBlockComment, 2, 3));
+    }
+
+    #[test]
+    fn displays_errors_with_their_position() {
+        assert_eq!(lex("x\n  0b102").unwrap_err().to_string(), "2:7: '2' is not a valid digit in base 2");
+        assert_eq!(lex("'a\\q'").unwrap_err().to_string(), "1:3: Invalid escape '\\q'");
+    }
+
+    #[test]
+    fn locates_the_end_of_input() {
+        let mut lexer = Lexer::new("let x = 1\n  y");
+        assert_eq!(lexer.by_ref().count(), 6);
+        let eof = lexer.eof();
+        assert_eq!(eof.kind, TokenKind::Eof);
+        assert_eq!((eof.span.start, eof.span.end), (13, 13));
+        assert_eq!((eof.line, eof.column), (2, 4));
+    }
+
+    fn lossless(input: &str) -> Vec<Token<'_>> {
+        let options = LexerOptions { lossless: true, ..LexerOptions::default() };
+        tokenize_with_options(input, &options).0
+    }
+
+    fn trivia_texts<'a>(trivia: &[Trivia<'a>]) -> Vec<(TriviaKind, &'a str)> {
+        trivia.iter().map(|trivia| (trivia.kind, trivia.text)).collect()
+    }
+
+    #[test]
+    fn attaches_trivia_up_to_the_line_break_as_trailing() {
+        let tokens = lossless("// header\nlet x = /* c */\n\n  1 // one\r\n\n");
+        assert_eq!(trivia_texts(&tokens[0].leading_trivia), vec![
+            (TriviaKind::LineComment, "// header"),
+            (TriviaKind::NewLine, "\n"),
+        ]);
+        assert_eq!(trivia_texts(&tokens[2].trailing_trivia), vec![
+            (TriviaKind::Whitespace, " "),
+            (TriviaKind::BlockComment, "/* c */"),
+            (TriviaKind::NewLine, "\n"),
+        ]);
+        assert_eq!(trivia_texts(&tokens[3].leading_trivia), vec![
+            (TriviaKind::NewLine, "\n"),
+            (TriviaKind::Whitespace, "  "),
+        ]);
+        assert_eq!(trivia_texts(&tokens[3].trailing_trivia), vec![
+            (TriviaKind::Whitespace, " "),
+            (TriviaKind::LineComment, "// one"),
+        ]);
+        // a terminating line break is a token of its own
+        assert_eq!((&tokens[4].kind, tokens[4].text), (&TokenKind::NewLine, "\r\n"));
+        assert!(tokens[4].trailing_trivia.is_empty());
+        assert_eq!(trivia_texts(&tokens[5].leading_trivia), vec![(TriviaKind::NewLine, "\n")]);
+        assert_eq!(tokens[5].kind, TokenKind::Eof);
+    }
+
+    #[test]
+    fn keeps_source_text_of_cooked_tokens() {
+        let tokens = lossless("'a\\n${ b }' \"\"\"\n  x\n  \"\"\"");
+        let texts: Vec<&str> = tokens.iter().map(|token| token.text).collect();
+        assert_eq!(texts, vec!["'", "a\\n", "${", "b", "}", "'", "\"\"\"\n  x\n  \"\"\"", ""]);
+    }
+
+    #[test]
+    fn does_not_keep_trivia_by_default() {
+        let tokens = lex(" x // y").unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert!(tokens[0].leading_trivia.is_empty() && tokens[0].trailing_trivia.is_empty());
+    }
+
+    #[test]
+    fn reproduces_invalid_input() {
+        for input in ["1 0x 'a\\q", "x /* open", "a & \u{7f} '${ \"b", "\r\r\n\t r#'"] {
+            assert_eq!(source_text(&lossless(input)), input);
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn reproduces_any_input_byte_for_byte(input in "(?s).{0,64}") {
+            proptest::prop_assert_eq!(source_text(&lossless(&input)), input);
+        }
+
+        #[test]
+        fn reproduces_source_like_input_byte_for_byte(input in "[a-z0-9_ \t\r\n/*'\"${}()=+.#r\\\\é]{0,64}") {
+            proptest::prop_assert_eq!(source_text(&lossless(&input)), input);
+        }
+    }
+
+    #[test]
+    fn streams_tokens_and_errors_in_source_order() {
+        let items: Vec<Result<&str, LexerErrorType>> = Lexer::new("a # b\n'c")
+            .map(|item| item.map(|token| token.text).map_err(|error| error.error_type))
+            .collect();
+        assert_eq!(items, vec![
+            Ok("a"),
+            Err(LexerErrorType::UnexpectedCharacter('#')),
+            Ok("#"),
+            Ok("b"),
+            Ok("\n"),
+            Err(LexerErrorType::UnterminatedString),
+            Ok("'c"),
+        ]);
+    }
+
+    #[test]
+    fn borrows_values_unless_lexing_changed_them() {
+        let tokens: Vec<Token> = Lexer::new("x 'plain' 'esc\\n' r#'r\\aw'# 12u8").map(Result::unwrap).collect();
+        let borrowed: Vec<bool> = tokens.iter().map(|token| matches!(token.value, Cow::Borrowed(_))).collect();
+        assert_eq!(borrowed, vec![true, true, false, true, true]);
+        assert_eq!(tokens[1].value, "plain");
+        assert_eq!(tokens[2].value, "esc\n");
+        assert_eq!(tokens[3].value, "r\\aw");
+    }
+
+    #[test]
+    fn lexes_lazily() {
+        let mut lexer = Lexer::new("let x = 1 /* never closed");
+        assert_eq!(lexer.next().unwrap().unwrap().kind, TokenKind::Keyword(Keyword::Let));
+        assert_eq!(lexer.cursor.offset(), 3);
+    }
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        lex(input).unwrap().into_iter().map(|token| token.kind).collect()
+    }
+
+    #[test]
+    fn terminates_lines_after_tokens_that_end_an_expression() {
+        use TokenKind::{NewLine, Operator, Symbol};
+        assert_eq!(kinds("a\nb"), vec![Symbol, NewLine, Symbol]);
+        assert_eq!(kinds("f()\n\n// c\n\nb"), vec![Symbol, Operator, Operator, NewLine, Symbol]);
+        assert_eq!(kinds("a =\nb"), vec![Symbol, Operator, Symbol]);
+        assert_eq!(kinds("{\na\n}"), vec![Operator, Symbol, NewLine, Operator]);
+        assert_eq!(kinds("\na;\nb"), vec![Symbol, Operator, Symbol]);
+    }
+
+    #[test]
+    fn does_not_terminate_lines_inside_parentheses_or_holes() {
+        use TokenKind::{NewLine, Operator, Symbol};
+        assert_eq!(kinds("f(a\n, b\n)\n"), vec![Symbol, Operator, Symbol, Operator, Symbol, Operator, NewLine]);
+        assert_eq!(kinds("(a\n{ b\n })"), vec![Operator, Symbol, Operator, Symbol, NewLine, Operator, Operator]);
+        assert_eq!(kinds("'${a\n}'").len(), 5);
+    }
+
+    #[test]
+    fn interns_identifiers_and_strings() {
+        let tokens: Vec<Token> = Lexer::new("name = 'name' + 'a${name}'").map(Result::unwrap).collect();
+        let symbols: Vec<Option<Symbol>> = tokens.iter().map(|token| token.symbol).collect();
+        let name = Some(Symbol::intern("name"));
+        assert_eq!(symbols, vec![name, None, name, None, None, Some(Symbol::intern("a")), None, name, None, None]);
+    }
+}
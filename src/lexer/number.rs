@@ -0,0 +1,217 @@
+// number:
+//     ('0x' hex_digits | '0o' oct_digits | '0b' bin_digits | decimal) suffix?
+// decimal:
+//     digits ('.' digits)? (('e' | 'E') ('+' | '-')? digits)?
+
+use std::fmt;
+
+use super::cursor::Cursor;
+use super::{LexerError, LexerErrorType, TokenKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegerSuffix {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl IntegerSuffix {
+    pub const ALL: [IntegerSuffix; 8] = [
+        IntegerSuffix::I8,
+        IntegerSuffix::I16,
+        IntegerSuffix::I32,
+        IntegerSuffix::I64,
+        IntegerSuffix::U8,
+        IntegerSuffix::U16,
+        IntegerSuffix::U32,
+        IntegerSuffix::U64,
+    ];
+
+    pub fn lookup(suffix: &str) -> Option<IntegerSuffix> {
+        IntegerSuffix::ALL.iter().copied().find(|s| s.as_str() == suffix)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IntegerSuffix::I8 => "i8",
+            IntegerSuffix::I16 => "i16",
+            IntegerSuffix::I32 => "i32",
+            IntegerSuffix::I64 => "i64",
+            IntegerSuffix::U8 => "u8",
+            IntegerSuffix::U16 => "u16",
+            IntegerSuffix::U32 => "u32",
+            IntegerSuffix::U64 => "u64",
+        }
+    }
+
+    /// The largest literal that fits in the suffixed type.
+    pub fn max_value(&self) -> u64 {
+        match self {
+            IntegerSuffix::I8 => i8::MAX as u64,
+            IntegerSuffix::I16 => i16::MAX as u64,
+            IntegerSuffix::I32 => i32::MAX as u64,
+            IntegerSuffix::I64 => i64::MAX as u64,
+            IntegerSuffix::U8 => u8::MAX as u64,
+            IntegerSuffix::U16 => u16::MAX as u64,
+            IntegerSuffix::U32 => u32::MAX as u64,
+            IntegerSuffix::U64 => u64::MAX,
+        }
+    }
+
+    /// The largest literal that fits in the suffixed type with a `-`
+    /// before it, which for a signed type is one more than `max_value`.
+    pub fn max_negated(&self) -> u64 {
+        match self {
+            IntegerSuffix::I8 | IntegerSuffix::I16 | IntegerSuffix::I32 | IntegerSuffix::I64 => self.max_value() + 1,
+            _ => self.max_value(),
+        }
+    }
+}
+
+impl fmt::Display for IntegerSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatSuffix {
+    F32,
+    F64,
+}
+
+impl FloatSuffix {
+    pub fn lookup(suffix: &str) -> Option<FloatSuffix> {
+        match suffix {
+            "f32" => Some(FloatSuffix::F32),
+            "f64" => Some(FloatSuffix::F64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FloatSuffix::F32 => "f32",
+            FloatSuffix::F64 => "f64",
+        }
+    }
+}
+
+impl fmt::Display for FloatSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Splits the text of an integer token into its radix, its digits with the
+/// `_` separators removed, and its suffix.
+pub fn split_integer(text: &str) -> (u32, String, Option<IntegerSuffix>) {
+    let (radix, rest) = match text.get(..2) {
+        Some("0x") => (16, &text[2..]),
+        Some("0o") => (8, &text[2..]),
+        Some("0b") => (2, &text[2..]),
+        _ => (10, text),
+    };
+    let suffix = IntegerSuffix::ALL.iter().copied().find(|s| rest.ends_with(s.as_str()));
+    let digits = match suffix {
+        Some(suffix) => &rest[..rest.len() - suffix.as_str().len()],
+        None => rest,
+    };
+    (radix, digits.replace('_', ""), suffix)
+}
+
+/// Splits the text of a float token into its digits with the `_`
+/// separators removed and its suffix.
+pub fn split_float(text: &str) -> (String, Option<FloatSuffix>) {
+    let suffix = [FloatSuffix::F32, FloatSuffix::F64].into_iter().find(|s| text.ends_with(s.as_str()));
+    let digits = match suffix {
+        Some(suffix) => &text[..text.len() - suffix.as_str().len()],
+        None => text,
+    };
+    (digits.replace('_', ""), suffix)
+}
+
+/// Lexes a numeric literal starting at an ASCII digit and returns its kind.
+/// The token text is the literal exactly as written; `split_integer` and
+/// `split_float` take it apart.
+pub fn lex_number(cursor: &mut Cursor) -> Result<TokenKind, LexerError> {
+    let start = cursor.offset();
+    let radix = match (cursor.peek(), cursor.peek_second()) {
+        (Some('0'), Some('x')) => 16,
+        (Some('0'), Some('o')) => 8,
+        (Some('0'), Some('b')) => 2,
+        _ => 10,
+    };
+    if radix != 10 {
+        cursor.next();
+        cursor.next();
+    }
+
+    if take_digits(cursor, radix)? == 0 {
+        return Err(cursor.error(LexerErrorType::MissingDigits, start));
+    }
+
+    let mut is_float = false;
+    if radix == 10 {
+        let mut ahead = cursor.clone();
+        if ahead.next() == Some('.') && ahead.peek().is_some_and(|c| c.is_ascii_digit()) {
+            cursor.next();
+            take_digits(cursor, 10)?;
+            is_float = true;
+        }
+
+        let mut ahead = cursor.clone();
+        if let Some('e' | 'E') = ahead.next() {
+            if let Some('+' | '-') = ahead.peek() {
+                ahead.next();
+            }
+            if ahead.peek().is_some_and(|c| c.is_ascii_digit()) {
+                *cursor = ahead;
+                take_digits(cursor, 10)?;
+                is_float = true;
+            }
+        }
+    }
+
+    let suffix_start = cursor.offset();
+    if cursor.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+        while cursor.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            cursor.next();
+        }
+    }
+    let suffix = cursor.slice_from(suffix_start);
+    if !suffix.is_empty() {
+        if FloatSuffix::lookup(suffix).is_some() && radix == 10 {
+            is_float = true;
+        } else if IntegerSuffix::lookup(suffix).is_none() || is_float {
+            return Err(cursor.error(LexerErrorType::InvalidNumberSuffix(suffix.to_string()), suffix_start));
+        }
+    }
+
+    Ok(if is_float { TokenKind::Float } else { TokenKind::Integer })
+}
+
+/// Consumes digits of the given radix and `_` separators, returning how many
+/// actual digits were read. Decimal digits beyond the radix are an error
+/// rather than the end of the literal, so `0b102` is rejected as a whole.
+fn take_digits(cursor: &mut Cursor, radix: u32) -> Result<usize, LexerError> {
+    let mut count = 0;
+    while let Some(c) = cursor.peek() {
+        let start = cursor.offset();
+        if c.is_digit(radix) {
+            count += 1;
+        } else if c.is_ascii_digit() {
+            cursor.next();
+            return Err(cursor.error(LexerErrorType::InvalidDigit { digit: c, radix }, start));
+        } else if c != '_' {
+            break;
+        }
+        cursor.next();
+    }
+    Ok(count)
+}
@@ -0,0 +1,233 @@
+/// Identifies a file registered with a `SourceMap`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FileId(pub u32);
+
+/// A half-open byte range `start..end` into one source file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub file: FileId,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Span {
+    pub fn new(file: FileId, start: u32, end: u32) -> Self {
+        Span { file, start, end }
+    }
+
+    pub fn len(&self) -> u32 {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// The smallest span covering both `self` and `other`.
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.file, self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+/// A 1-based line and column. Whether the column counts UTF-8 bytes or
+/// UTF-16 code units depends on which `LineIndex` method produced it; a tab
+/// is one unit either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineCol {
+    pub line: u32,
+    pub column: u32,
+}
+
+/// Byte offsets of the start of every line in a text. `\n`, `\r\n` and a
+/// lone `\r` each end a line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineIndex {
+    line_starts: Vec<u32>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        let bytes = text.as_bytes();
+        for (i, byte) in bytes.iter().enumerate() {
+            let ends_line = match byte {
+                b'\n' => true,
+                b'\r' => bytes.get(i + 1) != Some(&b'\n'),
+                _ => false,
+            };
+            if ends_line {
+                line_starts.push(i as u32 + 1);
+            }
+        }
+        LineIndex { line_starts }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The byte offset where the 1-based `line` starts.
+    pub fn line_start(&self, line: u32) -> Option<u32> {
+        self.line_starts.get(line.checked_sub(1)? as usize).copied()
+    }
+
+    /// The 1-based line containing `offset` and the offset of its start.
+    fn line_of(&self, offset: u32) -> (u32, u32) {
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        (line as u32 + 1, self.line_starts[line])
+    }
+
+    /// Line and column of `offset`, with the column counted in UTF-8 bytes.
+    pub fn line_col(&self, offset: u32) -> LineCol {
+        let (line, start) = self.line_of(offset);
+        LineCol { line, column: offset - start + 1 }
+    }
+
+    /// Like `line_col`, but scans forward from the 0-based line `hint` and
+    /// leaves it at the line found. Looking up offsets in increasing order
+    /// this way avoids a binary search per lookup.
+    pub(crate) fn line_col_from(&self, offset: u32, hint: &mut usize) -> LineCol {
+        if self.line_starts.get(*hint).is_none_or(|start| *start > offset) {
+            *hint = self.line_of(offset).0 as usize - 1;
+        }
+        while self.line_starts.get(*hint + 1).is_some_and(|start| *start <= offset) {
+            *hint += 1;
+        }
+        LineCol { line: *hint as u32 + 1, column: offset - self.line_starts[*hint] + 1 }
+    }
+
+    /// Line and column of `offset` in `text`, with the column counted in
+    /// UTF-16 code units as editors speaking LSP expect.
+    pub fn line_col_utf16(&self, text: &str, offset: u32) -> LineCol {
+        let (line, start) = self.line_of(offset);
+        let units: usize = text[start as usize..offset as usize].chars().map(char::len_utf16).sum();
+        LineCol { line, column: units as u32 + 1 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    pub name: String,
+    pub text: String,
+    lines: LineIndex,
+}
+
+impl SourceFile {
+    pub fn new(name: String, text: String) -> Self {
+        let lines = LineIndex::new(&text);
+        SourceFile { name, text, lines }
+    }
+
+    pub fn lines(&self) -> &LineIndex {
+        &self.lines
+    }
+
+    pub fn line_col(&self, offset: u32) -> LineCol {
+        self.lines.line_col(offset)
+    }
+
+    pub fn line_col_utf16(&self, offset: u32) -> LineCol {
+        self.lines.line_col_utf16(&self.text, offset)
+    }
+
+    pub fn slice(&self, span: Span) -> &str {
+        &self.text[span.start as usize..span.end as usize]
+    }
+}
+
+/// Owns the text of every file in a session and turns spans back into
+/// human-readable positions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap::default()
+    }
+
+    pub fn add_file(&mut self, name: impl Into<String>, text: impl Into<String>) -> FileId {
+        self.files.push(SourceFile::new(name.into(), text.into()));
+        FileId(self.files.len() as u32 - 1)
+    }
+
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0 as usize]
+    }
+
+    /// Line and column of the start of `span`, counted in UTF-8 bytes.
+    pub fn line_col(&self, span: Span) -> LineCol {
+        self.file(span.file).line_col(span.start)
+    }
+
+    /// Line and column of the start of `span`, counted in UTF-16 code units.
+    pub fn line_col_utf16(&self, span: Span) -> LineCol {
+        self.file(span.file).line_col_utf16(span.start)
+    }
+}
+
+#[cfg(test)]
+mod test_span {
+    use super::*;
+
+    fn position(line: u32, column: u32) -> LineCol {
+        LineCol { line, column }
+    }
+
+    #[test]
+    fn finds_lines_for_every_line_ending() {
+        let index = LineIndex::new("a\nb\r\nc\rd");
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.line_col(0), position(1, 1));
+        assert_eq!(index.line_col(2), position(2, 1));
+        // the '\n' of a CRLF still belongs to the line it ends
+        assert_eq!(index.line_col(4), position(2, 3));
+        assert_eq!(index.line_col(5), position(3, 1));
+        assert_eq!(index.line_col(7), position(4, 1));
+    }
+
+    #[test]
+    fn finds_lines_from_a_hint() {
+        let index = LineIndex::new("a\nb\n\nc");
+        let mut hint = 0;
+        assert_eq!(index.line_col_from(4, &mut hint), position(3, 1));
+        assert_eq!(index.line_col_from(5, &mut hint), position(4, 1));
+        assert_eq!(index.line_col_from(2, &mut hint), position(2, 1));
+        assert_eq!(hint, 1);
+    }
+
+    #[test]
+    fn counts_tabs_as_one_column() {
+        let index = LineIndex::new("\t\tx");
+        assert_eq!(index.line_col(2), position(1, 3));
+    }
+
+    #[test]
+    fn counts_multi_byte_characters_in_utf8_and_utf16() {
+        // 'é' is 2 bytes / 1 unit, '😀' is 4 bytes / 2 units
+        let text = "é😀x\n😀y";
+        let index = LineIndex::new(text);
+        assert_eq!(index.line_col(6), position(1, 7));
+        assert_eq!(index.line_col_utf16(text, 6), position(1, 4));
+        assert_eq!(index.line_col(12), position(2, 5));
+        assert_eq!(index.line_col_utf16(text, 12), position(2, 3));
+    }
+
+    #[test]
+    fn maps_spans_through_the_source_map() {
+        let mut map = SourceMap::new();
+        let first = map.add_file("a.trk", "let a = 1");
+        let second = map.add_file("b.trk", "x\n  y");
+        assert_eq!(second, FileId(1));
+        assert_eq!(map.line_col(Span::new(first, 4, 5)), position(1, 5));
+        assert_eq!(map.line_col(Span::new(second, 4, 5)), position(2, 3));
+        assert_eq!(map.file(second).slice(Span::new(second, 4, 5)), "y");
+    }
+
+    #[test]
+    fn joins_spans() {
+        let span = Span::new(FileId(0), 4, 6).to(Span::new(FileId(0), 1, 2));
+        assert_eq!((span.start, span.end, span.len()), (1, 6, 5));
+    }
+}
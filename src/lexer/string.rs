@@ -0,0 +1,252 @@
+// string:
+//     '\'' (char | escape | hole)* '\'' | '"' (char | escape | hole)* '"'
+// escape:
+//     '\' ('n' | 't' | 'r' | '0' | '\' | '\'' | '"' | '$') | '\u{' [0-9a-fA-F]{1,6} '}'
+// hole:
+//     '${' expression '}'
+// triple_quoted:
+//     '\'\'\'' (char | escape)* '\'\'\'' | '"""' (char | escape)* '"""'
+// raw:
+//     'r' '#'* quote char* quote '#'*
+
+use super::cursor::Cursor;
+use super::{LexerError, LexerErrorType};
+
+/// How a run of string characters ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FragmentEnd {
+    /// The closing quote was consumed.
+    Closed,
+    /// A `${` was consumed and an interpolated expression follows.
+    Hole,
+}
+
+/// Lexes string characters up to the closing `quote` or the next `${` and
+/// returns them with escapes resolved. The opening quote, at offset
+/// `string_start`, has already been consumed. Only an unterminated string
+/// is returned as an error; invalid escapes are recorded in `errors`.
+pub fn lex_fragment(
+    cursor: &mut Cursor,
+    quote: char,
+    string_start: usize,
+    errors: &mut Vec<LexerError>,
+) -> Result<(String, FragmentEnd), LexerError> {
+    let mut value = String::new();
+    loop {
+        match cursor.next() {
+            Some(c) if c == quote => {
+                return Ok((value, FragmentEnd::Closed));
+            }
+            Some('$') if cursor.peek() == Some('{') => {
+                cursor.next();
+                return Ok((value, FragmentEnd::Hole));
+            }
+            Some('\\') => {
+                push_escape(cursor, string_start, &mut value, errors)?;
+            }
+            Some(c) => {
+                value.push(c);
+            }
+            None => {
+                return Err(unterminated(cursor, string_start));
+            }
+        }
+    }
+}
+
+/// One line of a triple-quoted string: its literal leading whitespace and
+/// the rest of the line with escapes resolved.
+#[derive(Default)]
+struct IndentedLine {
+    indent: String,
+    text: String,
+}
+
+/// Lexes a `'''` or `"""` string, which may span lines. A line break right
+/// after the opening quotes and a whitespace-only line before the closing
+/// quotes are dropped, and the leading whitespace common to all non-blank
+/// lines is stripped. Escapes are resolved but `${` is not interpolated.
+pub fn lex_triple_quoted(cursor: &mut Cursor, errors: &mut Vec<LexerError>) -> Result<String, LexerError> {
+    let start = cursor.offset();
+    let quote = match cursor.peek() {
+        Some(quote) => quote,
+        None => {
+            return Err(unterminated(cursor, start));
+        }
+    };
+    for _ in 0..3 {
+        cursor.next();
+    }
+
+    let mut lines = vec![IndentedLine::default()];
+    loop {
+        let current = lines.last_mut().unwrap();
+        match cursor.next() {
+            Some(c) if c == quote && cursor.clone().take(2).filter(|c| *c == quote).count() == 2 => {
+                cursor.next();
+                cursor.next();
+                break;
+            }
+            Some('\r') if cursor.peek() == Some('\n') => {}
+            Some('\n') => {
+                lines.push(IndentedLine::default());
+            }
+            Some(c @ (' ' | '\t')) if current.text.is_empty() => {
+                current.indent.push(c);
+            }
+            Some('\\') => {
+                push_escape(cursor, start, &mut current.text, errors)?;
+            }
+            Some(c) => {
+                current.text.push(c);
+            }
+            None => {
+                return Err(unterminated(cursor, start));
+            }
+        }
+    }
+
+    if lines.len() > 1 && lines[0].text.is_empty() {
+        lines.remove(0);
+    }
+    if lines.len() > 1 && lines.last().is_some_and(|line| line.text.is_empty()) {
+        lines.pop();
+    }
+
+    let common_indent = lines
+        .iter()
+        .filter(|line| !line.text.is_empty())
+        .map(|line| line.indent.as_str())
+        .reduce(|common, indent| {
+            let shared = common.chars().zip(indent.chars()).take_while(|(a, b)| a == b).count();
+            &common[..shared]
+        })
+        .unwrap_or("")
+        .len();
+
+    let lines: Vec<String> = lines
+        .into_iter()
+        .map(|line| match line.text.is_empty() {
+            true => String::new(),
+            false => format!("{}{}", &line.indent[common_indent..], line.text),
+        })
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+/// Lexes a raw string starting at its `r` prefix. Its contents are taken
+/// verbatim up to the quote that is followed by as many `#` as preceded the
+/// opening quote.
+pub fn lex_raw(cursor: &mut Cursor) -> Result<String, LexerError> {
+    let start = cursor.offset();
+    cursor.next();
+
+    let mut hashes = 0;
+    while cursor.peek() == Some('#') {
+        cursor.next();
+        hashes += 1;
+    }
+    let quote_start = cursor.offset();
+    let quote = match cursor.next() {
+        Some(quote @ ('\'' | '"')) => quote,
+        Some(c) => {
+            return Err(cursor.error(LexerErrorType::UnexpectedCharacter(c), quote_start));
+        }
+        None => {
+            return Err(unterminated(cursor, start));
+        }
+    };
+
+    let mut value = String::new();
+    loop {
+        match cursor.next() {
+            Some(c) if c == quote && cursor.clone().take(hashes).filter(|c| *c == '#').count() == hashes => {
+                for _ in 0..hashes {
+                    cursor.next();
+                }
+                return Ok(value);
+            }
+            Some(c) => {
+                value.push(c);
+            }
+            None => {
+                return Err(unterminated(cursor, start));
+            }
+        }
+    }
+}
+
+/// Resolves the escape following a `\` into `value`. An invalid escape is
+/// recorded in `errors` and kept verbatim so lexing carries on inside the
+/// string.
+fn push_escape(cursor: &mut Cursor, string_start: usize, value: &mut String, errors: &mut Vec<LexerError>) -> Result<(), LexerError> {
+    let start = cursor.offset() - 1;
+    match lex_escape(cursor, string_start) {
+        Ok(c) => value.push(c),
+        Err(error) if error.error_type == LexerErrorType::UnterminatedString => return Err(error),
+        Err(error) => {
+            errors.push(error);
+            value.push_str(cursor.slice_from(start));
+        }
+    }
+    Ok(())
+}
+
+/// Resolves the escape following a `\`, which has already been consumed.
+/// Errors cover the escape itself.
+fn lex_escape(cursor: &mut Cursor, string_start: usize) -> Result<char, LexerError> {
+    let start = cursor.offset() - 1;
+    let c = match cursor.next() {
+        Some(c) => c,
+        None => {
+            return Err(unterminated(cursor, string_start));
+        }
+    };
+    match c {
+        'n' => Ok('\n'),
+        't' => Ok('\t'),
+        'r' => Ok('\r'),
+        '0' => Ok('\0'),
+        '\\' | '\'' | '"' | '$' => Ok(c),
+        'u' => lex_unicode_escape(cursor, start, string_start),
+        _ => Err(cursor.error(LexerErrorType::InvalidEscape(c), start)),
+    }
+}
+
+/// Resolves the `{XXXX}` part of a `\u{XXXX}` escape starting at `start`.
+fn lex_unicode_escape(cursor: &mut Cursor, start: usize, string_start: usize) -> Result<char, LexerError> {
+    if cursor.peek() != Some('{') {
+        return Err(cursor.error(LexerErrorType::InvalidUnicodeEscape, start));
+    }
+    cursor.next();
+
+    let digits_start = cursor.offset();
+    loop {
+        match cursor.peek() {
+            Some('}') => {
+                break;
+            }
+            Some(c) if c.is_ascii_hexdigit() && cursor.offset() - digits_start < 6 => {
+                cursor.next();
+            }
+            Some(_) => {
+                return Err(cursor.error(LexerErrorType::InvalidUnicodeEscape, start));
+            }
+            None => {
+                return Err(unterminated(cursor, string_start));
+            }
+        }
+    }
+    let digits = cursor.slice_from(digits_start);
+    cursor.next();
+
+    u32::from_str_radix(digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| cursor.error(LexerErrorType::InvalidUnicodeEscape, start))
+}
+
+/// An unterminated string error pointing at the opening quote.
+fn unterminated(cursor: &Cursor, string_start: usize) -> LexerError {
+    cursor.error_at(LexerErrorType::UnterminatedString, cursor.span(string_start, string_start + 1))
+}
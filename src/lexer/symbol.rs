@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+/// An interned string. Equal strings intern to the same `Symbol`, so names
+/// compare and hash as a `u32`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Interns `text` in the global interner, where it stays for the rest
+    /// of the process.
+    pub fn intern(text: &str) -> Symbol {
+        Interner::global().lock().unwrap().intern(text)
+    }
+
+    /// The text this symbol was interned from.
+    pub fn as_str(self) -> &'static str {
+        Interner::global().lock().unwrap().resolve(self)
+    }
+
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(text: &str) -> Self {
+        Symbol::intern(text)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Hands out a `Symbol` for each distinct string. There is one interner,
+/// `Interner::global`, since symbols always resolve against it.
+///
+/// Interned text is leaked, which lets `resolve` return it as
+/// `&'static str`. Memory grows with every distinct string ever interned,
+/// so a long-running process that lexes changing text, such as an editor
+/// calling `relex` on each keystroke, keeps every name it has seen.
+#[derive(Debug)]
+pub struct Interner {
+    symbols: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+}
+
+impl Interner {
+    fn new() -> Self {
+        Interner { symbols: HashMap::new(), strings: Vec::new() }
+    }
+
+    /// The interner behind `Symbol::intern`, shared by the whole process.
+    pub fn global() -> &'static Mutex<Interner> {
+        static GLOBAL: OnceLock<Mutex<Interner>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Mutex::new(Interner::new()))
+    }
+
+    pub fn intern(&mut self, text: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(text) {
+            return *symbol;
+        }
+        let text: &'static str = Box::leak(text.to_string().into_boxed_str());
+        let symbol = Symbol(self.strings.len() as u32);
+        self.strings.push(text);
+        self.symbols.insert(text, symbol);
+        symbol
+    }
+
+    /// The text of `symbol`. Panics if it came from another interner.
+    pub fn resolve(&self, symbol: Symbol) -> &'static str {
+        self.strings[symbol.0 as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test_symbol {
+    use super::*;
+
+    #[test]
+    fn interns_equal_strings_to_the_same_symbol() {
+        let mut interner = Interner::new();
+        let a = interner.intern("add");
+        let b = interner.intern("sub");
+        assert_eq!(interner.intern(&String::from("add")), a);
+        assert_ne!(a, b);
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn resolves_symbols_back_to_text() {
+        let mut interner = Interner::new();
+        let symbol = interner.intern("größe");
+        assert_eq!(interner.resolve(symbol), "größe");
+    }
+
+    #[test]
+    fn shares_the_global_interner() {
+        let symbol = Symbol::intern("shared_name");
+        assert_eq!(Symbol::from("shared_name"), symbol);
+        assert_eq!(symbol.as_str(), "shared_name");
+        assert_eq!(format!("{} {:?}", symbol, symbol), "shared_name \"shared_name\"");
+    }
+}
@@ -1,22 +1,14 @@
-mod lexer;
-mod parse;
+use trek_lang::{lexer, parse};
 
 fn main() {
-    let tokens = match lexer::tokenize("let add = (a b) => print(\'Hello World\')".to_string()) {
-        Ok(tokens) => tokens,
-        Err(e) => {
-            println!("{:?}", e);
-            return;
-        }
-    };
-
-    let module = parse::parse_module(&mut tokens.clone());
-    match module {
-        Ok(module) => {
-            println!("{:?}", module);
-        }
-        Err(e) => {
-            println!("{:?}", e);
-        }
+    let source = "let add (a b) => print(\'Hello World\')";
+    let (module, errors, lexer_errors) = parse::parse_module(lexer::Lexer::new(source));
+    for e in &lexer_errors {
+        println!("{}", e);
+    }
+    for e in &errors {
+        println!("{}", e);
     }
+
+    println!("{:?}", module);
 }
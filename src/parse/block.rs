@@ -1,17 +1,18 @@
 // block:
-//  '{' expression* '}'
+//  '{' terminator* (expression (terminator+ expression)* terminator*)? '}'
+//
+// After an error, parsing resumes at the next newline, ';', 'let' or the
+// '}' closing the block, outside of brackets.
 
-use crate::lexer;
-
-use super::{Expression, Parse, ParseError};
+use super::{error_node, is_terminator, resumes_statement, synchronize, Expected, Expression, Parse, ParseError, Spanned, TokenCursor};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Block {
-    pub expressions: Vec<Expression>,
+    pub expressions: Vec<Spanned<Expression>>,
 }
 
 impl Block {
-    pub fn new(expressions: Vec<Expression>) -> Self {
+    pub fn new(expressions: Vec<Spanned<Expression>>) -> Self {
         Block {
             expressions,
         }
@@ -19,86 +20,180 @@ impl Block {
 }
 
 impl Parse for Block {
-    fn parse( tokens: &mut Vec<lexer::Token>) -> Result<Self, ParseError>
+    /// Parses a block, recovering from an error in one of its expressions
+    /// by reporting it and carrying on with the next, with an
+    /// `Expression::Error` standing in for the one that failed. An error
+    /// that runs into the end of input is returned instead.
+    fn parse(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError>
         where
             Self: Sized {
-        match tokens.last() {
-            Some(token) => {
-                if token.kind != lexer::TokenKind::Operator || token.value != "{" {
-                    return Err(ParseError::new("Expected '{'", token.line, token.column));
-                }
-                tokens.pop();
+        let start = tokens.expect_operator("{")?.span;
 
+        let mut expressions = Vec::new();
+        tokens.skip_terminators();
+        let end = loop {
+            if tokens.at_operator("}") {
+                break tokens.advance().span;
             }
-            None => {
-                return Err(ParseError::new("Expected '{'", 0, 0));
+            if tokens.is_at_end() {
+                return Err(ParseError::unexpected(tokens.peek(), vec![Expected::Token("}")]));
             }
-        }
-
-        let mut expressions = Vec::new();
-        loop {
-            match tokens.last() {
-                Some(token) => {
-                    if token.kind == lexer::TokenKind::Operator && token.value == "}" {
-                        tokens.pop();
-                        break;
+            let checkpoint = tokens.checkpoint();
+            match Expression::parse(tokens) {
+                Ok(expression) => expressions.push(expression),
+                Err(error) => {
+                    synchronize(tokens, checkpoint, resumes_statement);
+                    if tokens.is_at_end() {
+                        return Err(error);
                     }
+                    tokens.report(error);
+                    expressions.push(error_node(tokens, checkpoint));
+                    tokens.skip_terminators();
+                    continue;
                 }
-                None => {
-                    return Err(ParseError::new("Expected '}'", 0, 0));
+            }
+            let token = tokens.peek();
+            if is_terminator(token) {
+                tokens.skip_terminators();
+            } else if !tokens.at_operator("}") {
+                let error = ParseError::unexpected(token, vec![Expected::NewLine, Expected::Token(";"), Expected::Token("}")]);
+                synchronize(tokens, tokens.checkpoint(), resumes_statement);
+                if tokens.is_at_end() {
+                    return Err(error);
                 }
+                tokens.report(error);
+                tokens.skip_terminators();
             }
-            expressions.push(Expression::parse(tokens)?);
-        }
+        };
 
-        Ok(Block::new(expressions))
+        Ok(Spanned::new(Block::new(expressions), start.to(end)))
     }
 }
 
 #[cfg(test)]
 mod test_block {
     use super::*;
+    use crate::lexer;
+    use crate::parse::{identifier, lexed, parse_all, ParseErrorKind};
 
     #[test]
     fn returns_error_when_no_open_brace() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Operator, "}".to_string(), 0, 0),
-        ].into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Operator, "}", 0, 0),
+        ]);
         let block = Block::parse(&mut tokens);
         assert!(block.is_err());
-        assert_eq!(block.unwrap_err(), ParseError::new("Expected '{'", 0, 0));
+        assert_eq!(block.unwrap_err().kind, ParseErrorKind::UnexpectedToken { expected: vec![Expected::Token("{")], found: "}".to_string() });
     }
 
     #[test]
     fn returns_error_when_no_close_brace() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Operator, "{".to_string(), 0, 0),
-        ].into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Operator, "{", 0, 0),
+        ]);
         let block = Block::parse(&mut tokens);
         assert!(block.is_err());
-        assert_eq!(block.unwrap_err(), ParseError::new("Expected '}'", 0, 0));
+        assert_eq!(block.unwrap_err().kind, ParseErrorKind::UnexpectedEof { expected: vec![Expected::Token("}")] });
     }
 
     #[test]
     fn parses_an_empty_block() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Operator, "{".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Operator, "}".to_string(), 0, 0),
-        ].into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Operator, "{", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, "}", 0, 0),
+        ]);
         let block = Block::parse(&mut tokens);
         assert!(block.is_ok());
-        assert_eq!(block.unwrap(), Block::new(vec![]));
+        assert_eq!(block.unwrap().node, Block::new(vec![]));
     }
 
     #[test]
     fn parses_with_expressions() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Operator, "{".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Number, "1".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Operator, "}".to_string(), 0, 0),
-        ].into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Operator, "{", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Integer, "1", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, "}", 0, 0),
+        ]);
         let block = Block::parse(&mut tokens);
         assert!(block.is_ok());
-        assert_eq!(block.unwrap(), Block::new(vec![Expression::Number(1)]));
+        assert_eq!(block.unwrap().node, Block::new(vec![Expression::Integer(1.into()).into()]));
+    }
+
+    fn parse(input: &str) -> Result<Block, ParseError> {
+        parse_all(input, Block::parse).map(|block| block.node)
     }
-}
\ No newline at end of file
+
+    fn identifiers(names: &[&str]) -> Block {
+        Block::new(names.iter().map(|name| identifier(name)).collect())
+    }
+
+    #[test]
+    fn separates_expressions_by_newlines() {
+        assert_eq!(parse("{\n  a\n\n  b\n}"), Ok(identifiers(&["a", "b"])));
+    }
+
+    #[test]
+    fn separates_expressions_by_semicolons() {
+        assert_eq!(parse("{ a; b; }"), Ok(identifiers(&["a", "b"])));
+    }
+
+    #[test]
+    fn continues_expressions_after_an_operator() {
+        let block = parse("{\n  let x =\n    1\n}");
+        let block = block.unwrap();
+        assert!(matches!(&block.expressions[..], [Spanned { node: Expression::Definition(_), .. }]));
+    }
+
+    #[test]
+    fn does_not_call_across_a_newline() {
+        let block = parse("{\n  f\n  (a) => a\n}").unwrap();
+        assert!(matches!(
+            &block.expressions[..],
+            [Spanned { node: Expression::Identifier(_), .. }, Spanned { node: Expression::Lambda(_), .. }]
+        ));
+    }
+
+    #[test]
+    fn spans_from_brace_to_brace() {
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new("{ a }"));
+        let block = Block::parse(&mut tokens).unwrap();
+        assert_eq!((block.span.start, block.span.end), (0, 5));
+        assert_eq!((block.expressions[0].span.start, block.expressions[0].span.end), (2, 3));
+    }
+
+    #[test]
+    fn returns_error_for_expressions_on_one_line() {
+        let error = parse("{ a b }").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedToken {
+            expected: vec![Expected::NewLine, Expected::Token(";"), Expected::Token("}")],
+            found: "b".to_string(),
+        });
+        assert_eq!((error.line, error.column), (1, 5));
+    }
+
+    #[test]
+    fn recovers_at_the_next_expression_of_the_block() {
+        let mut tokens = lexed("{\n  let = 1\n  a\n  (b c)\n  d e; f\n}");
+        let block = Block::parse(&mut tokens).unwrap();
+        assert!(tokens.is_at_end());
+        assert_eq!(block.node.expressions.iter().map(|expression| &expression.node).collect::<Vec<_>>(), vec![
+            &Expression::Error,
+            &Expression::Identifier("a".into()),
+            &Expression::Identifier("b".into()),
+            &Expression::Identifier("d".into()),
+            &Expression::Identifier("f".into()),
+        ]);
+        let errors: Vec<(usize, usize)> = tokens.take_errors().into_iter().map(|error| (error.line, error.column)).collect();
+        assert_eq!(errors, vec![(2, 7), (4, 6), (5, 5)]);
+    }
+
+    #[test]
+    fn returns_error_at_the_end_of_input_for_unclosed_block() {
+        let (module, errors, _) = crate::parse::parse_module(lexer::Lexer::new("let f = {
+  a
+"));
+        assert_eq!(module.expressions.len(), 1);
+        assert_eq!(errors[0].kind, ParseErrorKind::UnexpectedEof { expected: vec![Expected::Token("}")] });
+        assert_eq!((errors[0].line, errors[0].column), (3, 1));
+    }
+}
@@ -0,0 +1,311 @@
+use crate::lexer::{Lexer, LexerError, Span, Symbol, Token, TokenKind};
+
+use super::fixity::FixityTable;
+use super::{expected_identifier, Expected, ParseError, ParseErrorKind, Spanned};
+
+/// A position in a `TokenCursor` to rewind to, along with how many errors
+/// had been reported there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    position: usize,
+    errors: usize,
+}
+
+/// The tokens being parsed, in source order, and the position of the next
+/// one. The tokens always end with an `Eof` token that the cursor never
+/// moves past, so lookahead never runs out and an error at the end of input
+/// still has a position. The cursor also holds the fixities of the
+/// operators the tokens declare, for parsing the uses of those operators,
+/// and collects the errors the parser recovers from.
+#[derive(Debug, Clone)]
+pub struct TokenCursor<'src> {
+    tokens: Vec<Token<'src>>,
+    position: usize,
+    fixities: FixityTable,
+    errors: Vec<ParseError>,
+}
+
+impl<'src> TokenCursor<'src> {
+    /// A cursor over `tokens`, which must be in source order. Without an
+    /// `Eof` token of their own they are given one just after the last
+    /// token.
+    pub fn new(tokens: Vec<Token<'src>>) -> Self {
+        let mut cursor = TokenCursor::without_fixities(tokens);
+        cursor.fixities = FixityTable::collect(&cursor.tokens);
+        cursor
+    }
+
+    /// A cursor over `tokens` that ignores any fixity declarations in them.
+    pub(super) fn without_fixities(mut tokens: Vec<Token<'src>>) -> Self {
+        if tokens.last().is_none_or(|token| token.kind != TokenKind::Eof) {
+            let eof = eof_after(tokens.last());
+            tokens.push(eof);
+        }
+        TokenCursor { tokens, position: 0, fixities: FixityTable::default(), errors: Vec::new() }
+    }
+
+    /// Lexes all of `lexer`, carrying on past bad input, into a cursor
+    /// whose `Eof` token is at the real end of input, along with every
+    /// lexer error. Operators the module declares lex as one token even
+    /// above their declaration.
+    pub fn lex(lexer: Lexer<'src>) -> (Self, Vec<LexerError>) {
+        let source = lexer.source();
+        let mut options = lexer.options().clone();
+        let (mut tokens, mut errors) = lex_all(lexer);
+        // a use above the declaration was split up before the lexer knew
+        // the operator, so lex again knowing every declared operator
+        let undeclared: Vec<String> = FixityTable::collect(&tokens)
+            .operators()
+            .filter(|operator| !options.operators.iter().any(|known| known == operator))
+            .map(str::to_string)
+            .collect();
+        if !undeclared.is_empty() {
+            options.operators.extend(undeclared);
+            (tokens, errors) = lex_all(Lexer::with_options(source, &options));
+        }
+        (TokenCursor::new(tokens), errors)
+    }
+
+    /// The next token, which is the `Eof` token at the end of input.
+    pub fn peek(&self) -> &Token<'src> {
+        self.peek_nth(0)
+    }
+
+    /// The token `n` places after the next one, or the `Eof` token if that
+    /// is past the end.
+    pub fn peek_nth(&self, n: usize) -> &Token<'src> {
+        let last = self.tokens.len() - 1;
+        &self.tokens[(self.position + n).min(last)]
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.peek().kind == TokenKind::Eof
+    }
+
+    /// Whether the next token is the operator or punctuation `text`.
+    pub fn at_operator(&self, text: &str) -> bool {
+        let token = self.peek();
+        token.kind == TokenKind::Operator && token.value == text
+    }
+
+    /// Moves past the next token and returns it. At the end of input the
+    /// `Eof` token is returned and the cursor stays put.
+    pub fn advance(&mut self) -> Token<'src> {
+        let token = self.peek().clone();
+        if token.kind != TokenKind::Eof {
+            self.position += 1;
+        }
+        token
+    }
+
+    /// Moves past the next token if it is of `kind`, and fails without
+    /// moving otherwise.
+    pub fn expect(&mut self, kind: TokenKind) -> Result<Token<'src>, ParseError> {
+        match self.peek().kind == kind {
+            true => Ok(self.advance()),
+            false => Err(ParseError::unexpected(self.peek(), vec![expected(&kind)])),
+        }
+    }
+
+    /// Moves past the next token if it is the operator or punctuation
+    /// `text`, and fails without moving otherwise.
+    pub fn expect_operator(&mut self, text: &'static str) -> Result<Token<'src>, ParseError> {
+        match self.at_operator(text) {
+            true => Ok(self.advance()),
+            false => Err(ParseError::unexpected(self.peek(), vec![Expected::Token(text)])),
+        }
+    }
+
+    /// Moves past the next token if it is an identifier, calling out
+    /// reserved words in the error otherwise.
+    pub fn expect_identifier(&mut self) -> Result<Spanned<Symbol>, ParseError> {
+        let token = self.peek();
+        match token.symbol {
+            Some(symbol) if token.kind == TokenKind::Symbol => {
+                let span = token.span;
+                self.advance();
+                Ok(Spanned::new(symbol, span))
+            }
+            _ => Err(expected_identifier(token)),
+        }
+    }
+
+    /// Moves past any `NewLine` and `;` tokens.
+    pub fn skip_terminators(&mut self) {
+        while super::is_terminator(self.peek()) {
+            self.advance();
+        }
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint { position: self.position, errors: self.errors.len() }
+    }
+
+    /// Moves back to `checkpoint`, to try another parse of the same tokens,
+    /// dropping the errors reported since.
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.position = checkpoint.position;
+        self.errors.truncate(checkpoint.errors);
+    }
+
+    /// Records `error`, which the parser has recovered from, unless it is at
+    /// an `Error` token, which the lexer has already reported.
+    pub fn report(&mut self, error: ParseError) {
+        if error.kind != ParseErrorKind::InvalidInput {
+            self.errors.push(error);
+        }
+    }
+
+    /// Takes every error reported so far, in the order they were reported.
+    pub fn take_errors(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// The fixities of the operators the tokens declare.
+    pub(super) fn fixities(&self) -> &FixityTable {
+        &self.fixities
+    }
+
+    /// The tokens moved past since `checkpoint`.
+    pub fn since(&self, checkpoint: Checkpoint) -> &[Token<'src>] {
+        &self.tokens[checkpoint.position.min(self.position)..self.position]
+    }
+
+    /// The tokens not yet moved past, ending with the `Eof` token.
+    pub fn remaining(&self) -> &[Token<'src>] {
+        &self.tokens[self.position..]
+    }
+}
+
+/// Every token and error of `lexer`, with the tokens ending in an `Eof`
+/// token at the real end of input.
+fn lex_all(mut lexer: Lexer) -> (Vec<Token>, Vec<LexerError>) {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for item in lexer.by_ref() {
+        match item {
+            Ok(token) => tokens.push(token),
+            Err(error) => errors.push(error),
+        }
+    }
+    if tokens.last().is_none_or(|token| token.kind != TokenKind::Eof) {
+        tokens.push(lexer.eof());
+    }
+    (tokens, errors)
+}
+
+/// What a token of `kind` is called in an error.
+fn expected(kind: &TokenKind) -> Expected {
+    match kind {
+        TokenKind::Integer => Expected::Integer,
+        TokenKind::Float => Expected::Float,
+        TokenKind::Operator => Expected::Operator,
+        TokenKind::Symbol => Expected::Identifier,
+        TokenKind::Keyword(keyword) => Expected::Token(keyword.as_str()),
+        TokenKind::NewLine => Expected::NewLine,
+        TokenKind::StringLiteral | TokenKind::InterpolationStart | TokenKind::InterpolationEnd => Expected::String,
+        TokenKind::StringFragment => Expected::StringText,
+        TokenKind::HoleStart => Expected::Token("${"),
+        TokenKind::HoleEnd => Expected::Token("}"),
+        TokenKind::Eof => Expected::EndOfInput,
+        TokenKind::Error => Expected::InvalidInput,
+    }
+}
+
+/// An `Eof` token just after `last`, or at the very start when there are no
+/// tokens. Columns count bytes, as the lexer's do.
+fn eof_after<'src>(last: Option<&Token<'src>>) -> Token<'src> {
+    let Some(last) = last else {
+        return Token::new(TokenKind::Eof, "", 1, 1);
+    };
+    let (line, column) = match last.text.rfind('\n') {
+        Some(index) => (last.line + last.text.matches('\n').count(), last.text.len() - index),
+        None => (last.line, last.column + last.text.len()),
+    };
+    let mut eof = Token::new(TokenKind::Eof, "", column, line);
+    eof.span = Span::new(last.span.file, last.span.end, last.span.end);
+    eof
+}
+
+#[cfg(test)]
+mod test_cursor {
+    use super::*;
+    use crate::parse::ParseErrorKind;
+
+    fn cursor(input: &str) -> TokenCursor<'_> {
+        let (cursor, errors) = TokenCursor::lex(Lexer::new(input));
+        assert!(errors.is_empty());
+        cursor
+    }
+
+    #[test]
+    fn looks_ahead_without_moving() {
+        let tokens = cursor("f(a)");
+        assert_eq!(tokens.peek().value, "f");
+        assert_eq!(tokens.peek_nth(1).value, "(");
+        assert_eq!(tokens.peek_nth(3).value, ")");
+        assert_eq!(tokens.peek_nth(9).kind, TokenKind::Eof);
+        assert_eq!(tokens.remaining().len(), 5);
+    }
+
+    #[test]
+    fn stays_at_the_end_of_input() {
+        let mut tokens = cursor("a");
+        assert_eq!(tokens.advance().value, "a");
+        assert_eq!(tokens.advance().kind, TokenKind::Eof);
+        assert!(tokens.is_at_end());
+    }
+
+    #[test]
+    fn rewinds_to_a_checkpoint() {
+        let mut tokens = cursor("(a b) => a");
+        let checkpoint = tokens.checkpoint();
+        tokens.advance();
+        tokens.advance();
+        tokens.rewind(checkpoint);
+        assert!(tokens.at_operator("("));
+    }
+
+    #[test]
+    fn drops_errors_reported_after_a_checkpoint_on_rewind() {
+        let mut tokens = cursor("a b");
+        tokens.report(ParseError::unexpected(tokens.peek(), vec![Expected::Expression]));
+        let checkpoint = tokens.checkpoint();
+        tokens.advance();
+        tokens.report(ParseError::unexpected(tokens.peek(), vec![Expected::Expression]));
+        tokens.rewind(checkpoint);
+        let errors = tokens.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].column, 1);
+        assert!(tokens.take_errors().is_empty());
+    }
+
+    #[test]
+    fn fails_to_expect_without_moving() {
+        let mut tokens = cursor("let x");
+        let error = tokens.expect_operator("(").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedToken { expected: vec![Expected::Token("(")], found: "let".to_string() });
+        assert_eq!(tokens.expect(TokenKind::Keyword(crate::lexer::Keyword::Let)).unwrap().value, "let");
+        assert_eq!(tokens.expect_identifier().unwrap().node, Symbol::from("x"));
+        let error = tokens.expect(TokenKind::Symbol).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedEof { expected: vec![Expected::Identifier] });
+    }
+
+    #[test]
+    fn fails_to_expect_an_error_token_without_panicking() {
+        let mut tokens = cursor("a");
+        let error = tokens.expect(TokenKind::Error).unwrap_err();
+        assert_eq!(error.to_string(), "1:1: Expected invalid input, found 'a'");
+        assert_eq!(tokens.peek().value, "a");
+    }
+
+    #[test]
+    fn places_a_missing_eof_after_the_last_token() {
+        let tokens = vec![Token::new(TokenKind::Symbol, "abc", 3, 2), Token::new(TokenKind::NewLine, "\n", 6, 2)];
+        let end = TokenCursor::new(tokens).peek_nth(2).clone();
+        assert_eq!((end.kind, end.line, end.column), (TokenKind::Eof, 3, 1));
+        let tokens = vec![Token::new(TokenKind::Symbol, "abc", 3, 2)];
+        let end = TokenCursor::new(tokens).peek_nth(1).clone();
+        assert_eq!((end.line, end.column), (2, 6));
+    }
+}
@@ -1,16 +1,16 @@
-use super::{Parse, ParseError, Expression};
-use crate::lexer;
+use super::{lambda::Lambda, Expected, Parse, ParseError, Expression, Spanned, TokenCursor};
+use crate::lexer::{self, Symbol};
 
 
-// let: 'let' identifier '=' expression
+// let: 'let' identifier '=' expression | 'let' identifier lambda
 #[derive(Debug, Clone, PartialEq)]
 pub struct Definition {
-    pub identifier: String,
-    pub expression: Box<Expression>,
+    pub identifier: Spanned<Symbol>,
+    pub expression: Box<Spanned<Expression>>,
 }
 
 impl Definition {
-    pub fn new(identifier: String, expression: Box<Expression>) -> Self {
+    pub fn new(identifier: Spanned<Symbol>, expression: Box<Spanned<Expression>>) -> Self {
         Definition {
             identifier,
             expression,
@@ -19,110 +19,132 @@ impl Definition {
 }
 
 impl Parse for Definition {
-    fn parse(tokens: &mut Vec<lexer::Token>) -> Result<Self, ParseError>
+    fn parse(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError>
     where
         Self: Sized {
-            match tokens.last() {
-                Some(token) => {
-                    if token.kind != lexer::TokenKind::Symbol || token.value != "let" {
-                        println!("{:?}", token);
-                        return Err(ParseError::new("Expected 'let' keyword", token.line, token.column));
-                    }
-                    tokens.pop();
-                }
-                None => {
-                    return Err(ParseError::new("Expected 'let' keyword", 0, 0));
-                }
-            }
-
-            let identifier = match tokens.pop() {
-                Some(token) => {
-                    if token.kind != lexer::TokenKind::Symbol {
-                        return Err(ParseError::new("Expected identifier", token.line, token.column));
-                    }
-                    token.value
-                }
-                None => {
-                    return Err(ParseError::new("Expected identifier", 0, 0));
-                }
+            let start = tokens.expect(lexer::TokenKind::Keyword(lexer::Keyword::Let))?.span;
+
+            let identifier = tokens.expect_identifier()?;
+
+            // `let add (a b) => ...` defines `add` as the lambda
+            let expression = if tokens.at_operator("(") {
+                Lambda::parse(tokens)?.map(Expression::Lambda)
+            } else if tokens.at_operator("=") {
+                tokens.advance();
+                Expression::parse(tokens)?
+            } else {
+                return Err(ParseError::unexpected(tokens.peek(), vec![Expected::Token("="), Expected::Token("(")]));
             };
 
-            match tokens.last() {
-                Some(token) => {
-                    if token.kind != lexer::TokenKind::Operator || token.value != "=" {
-                        return Err(ParseError::new("Expected '='", token.line, token.column));
-                    }
-                    tokens.pop();
-                }
-                None => {
-                    return Err(ParseError::new("Expected '='", 0, 0));
-                }
-            }
-
-            let expression = Expression::parse(tokens)?;
-
-            return Ok(Definition::new(identifier, Box::new(expression)));
+            let span = start.to(expression.span);
+            Ok(Spanned::new(Definition::new(identifier, Box::new(expression)), span))
 
         }
 }
 
 #[cfg(test)]
 mod test_definition {
-    use crate::{lexer, parse::Parse};
-    use super::{Definition, Expression};
+    use crate::{lexer, parse::{lexed, Expected, Parse, ParseErrorKind, TokenCursor}};
+    use super::{Definition, Expression, Spanned};
 
     #[test]
     fn returns_error_if_not_let() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Symbol, "do".to_string(), 1, 1),
-            lexer::Token::new(lexer::TokenKind::Symbol, "something".to_string(), 1, 3),
-            lexer::Token::new(lexer::TokenKind::Operator, "=".to_string(), 1, 13),
-            lexer::Token::new(lexer::TokenKind::Number, "1".to_string(), 1, 15),
-        ].into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Symbol, "do", 1, 1),
+            lexer::Token::new(lexer::TokenKind::Symbol, "something", 1, 3),
+            lexer::Token::new(lexer::TokenKind::Operator, "=", 1, 13),
+            lexer::Token::new(lexer::TokenKind::Integer, "1", 1, 15),
+        ]);
 
         let result = Definition::parse(&mut tokens);
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err().message, "Expected 'let' keyword");
+        assert_eq!(result.unwrap_err().kind, ParseErrorKind::UnexpectedToken { expected: vec![Expected::Token("let")], found: "do".to_string() });
     }
 
     #[test]
     fn returns_error_if_no_identifier() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Symbol, "let".to_string(), 1, 1),
-            lexer::Token::new(lexer::TokenKind::Operator, "=".to_string(), 1, 5),
-            lexer::Token::new(lexer::TokenKind::Number, "1".to_string(), 1, 7),
-        ].into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Keyword(lexer::Keyword::Let), "let", 1, 1),
+            lexer::Token::new(lexer::TokenKind::Operator, "=", 1, 5),
+            lexer::Token::new(lexer::TokenKind::Integer, "1", 1, 7),
+        ]);
 
         let result = Definition::parse(&mut tokens);
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err().message, "Expected identifier");
+        assert_eq!(result.unwrap_err().kind, ParseErrorKind::UnexpectedToken { expected: vec![Expected::Identifier], found: "=".to_string() });
+    }
+
+    #[test]
+    fn returns_error_if_identifier_is_reserved() {
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Keyword(lexer::Keyword::Let), "let", 1, 1),
+            lexer::Token::new(lexer::TokenKind::Keyword(lexer::Keyword::Let), "let", 1, 5),
+            lexer::Token::new(lexer::TokenKind::Operator, "=", 1, 9),
+            lexer::Token::new(lexer::TokenKind::Integer, "1", 1, 11),
+        ]);
+
+        let result = Definition::parse(&mut tokens);
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().kind, ParseErrorKind::ReservedWord(lexer::Keyword::Let));
+    }
+
+    #[test]
+    fn returns_with_a_parsed_boolean() {
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Keyword(lexer::Keyword::Let), "let", 1, 1),
+            lexer::Token::new(lexer::TokenKind::Symbol, "x", 1, 5),
+            lexer::Token::new(lexer::TokenKind::Operator, "=", 1, 7),
+            lexer::Token::new(lexer::TokenKind::Keyword(lexer::Keyword::True), "true", 1, 9),
+        ]);
+
+        let result = Definition::parse(&mut tokens);
+        assert_eq!(result.unwrap().node, Definition::new("x".into(), Box::new(Expression::Boolean(true).into())));
     }
 
     #[test]
     fn returns_error_if_no_eq() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Symbol, "let".to_string(), 1, 1),
-            lexer::Token::new(lexer::TokenKind::Symbol, "x".to_string(), 1, 5),
-            lexer::Token::new(lexer::TokenKind::Number, "1".to_string(), 1, 7),
-        ].into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Keyword(lexer::Keyword::Let), "let", 1, 1),
+            lexer::Token::new(lexer::TokenKind::Symbol, "x", 1, 5),
+            lexer::Token::new(lexer::TokenKind::Integer, "1", 1, 7),
+        ]);
 
         let result = Definition::parse(&mut tokens);
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err().message, "Expected '='");
+        assert_eq!(result.unwrap_err().kind, ParseErrorKind::UnexpectedToken { expected: vec![Expected::Token("="), Expected::Token("(")], found: "1".to_string() });
     }
 
     #[test]
     fn returns_with_a_parsed_number() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Symbol, "let".to_string(), 1, 1),
-            lexer::Token::new(lexer::TokenKind::Symbol, "x".to_string(), 1, 5),
-            lexer::Token::new(lexer::TokenKind::Operator, "=".to_string(), 1, 7),
-            lexer::Token::new(lexer::TokenKind::Number, "1".to_string(), 1, 9),
-        ].into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Keyword(lexer::Keyword::Let), "let", 1, 1),
+            lexer::Token::new(lexer::TokenKind::Symbol, "x", 1, 5),
+            lexer::Token::new(lexer::TokenKind::Operator, "=", 1, 7),
+            lexer::Token::new(lexer::TokenKind::Integer, "1", 1, 9),
+        ]);
 
         let result = Definition::parse(&mut tokens);
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), Definition::new("x".to_string(), Box::new(Expression::Number(1))));
-        assert!(tokens.is_empty());
+        assert_eq!(result.unwrap().node, Definition::new("x".into(), Box::new(Expression::Integer(1.into()).into())));
+        assert!(tokens.is_at_end());
+    }
+
+    fn parse(input: &str) -> Spanned<Definition> {
+        let mut tokens = lexed(input);
+        let definition = Definition::parse(&mut tokens).unwrap();
+        assert!(tokens.is_at_end());
+        definition
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn defines_a_function_from_parameters_after_the_name() {
+        let definition = parse("let add (a b) => a + b");
+        assert_eq!(definition, parse("let add = (a b) => a + b"));
+        assert_eq!((definition.span.start, definition.span.end), (0, 22));
+    }
+
+    #[test]
+    fn defines_a_function_without_parameters() {
+        assert_eq!(parse("let main = => f(1)"), parse("let main () => f(1)"));
+    }
+}
@@ -0,0 +1,221 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::lexer::{self, Keyword, Span, TokenKind};
+
+/// Something the parser was looking for when it found something else.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expected {
+    /// A particular operator, punctuation mark or keyword, such as `)` or
+    /// `let`.
+    Token(&'static str),
+    Identifier,
+    Expression,
+    Operator,
+    Precedence,
+    /// A line break that ends an expression.
+    NewLine,
+    Integer,
+    Float,
+    String,
+    /// Text inside an interpolated string.
+    StringText,
+    EndOfInput,
+    /// Input the lexer could not read, which only an `Error` token holds.
+    InvalidInput,
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Token(text) => write!(f, "'{}'", text),
+            Expected::Identifier => f.write_str("identifier"),
+            Expected::Expression => f.write_str("expression"),
+            Expected::Operator => f.write_str("operator"),
+            Expected::Precedence => f.write_str("precedence"),
+            Expected::NewLine => f.write_str("newline"),
+            Expected::Integer => f.write_str("integer"),
+            Expected::Float => f.write_str("float"),
+            Expected::String => f.write_str("string"),
+            Expected::StringText => f.write_str("string text"),
+            Expected::EndOfInput => f.write_str("end of input"),
+            Expected::InvalidInput => f.write_str("invalid input"),
+        }
+    }
+}
+
+/// Why a literal could not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LiteralError {
+    /// The value does not fit the named type.
+    OutOfRange(&'static str),
+    Malformed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    /// `found` is the text of the token where one of `expected` should be.
+    UnexpectedToken { expected: Vec<Expected>, found: String },
+    UnexpectedEof { expected: Vec<Expected> },
+    /// A reserved word where an identifier should be.
+    ReservedWord(Keyword),
+    InvalidLiteral { literal: String, reason: LiteralError },
+    /// A fixity declaration's precedence outside 0 to 9.
+    InvalidPrecedence(String),
+    /// A fixity declaration for an operator the language already defines.
+    ReservedOperator(String),
+    DuplicateFixity(String),
+    /// Two operators of equal precedence that cannot group without
+    /// parentheses, as in `a < b < c`.
+    ChainedOperators { first: String, second: String },
+    /// A `}` outside of any block.
+    UnmatchedBrace,
+    /// An `Error` token where something else should be. The lexer has
+    /// already reported what is wrong with it.
+    InvalidInput,
+}
+
+/// Lists the alternatives in `expected` as "a, b or c".
+fn write_expected(f: &mut fmt::Formatter<'_>, expected: &[Expected]) -> fmt::Result {
+    for (index, item) in expected.iter().enumerate() {
+        if index > 0 {
+            f.write_str(if index == expected.len() - 1 { " or " } else { ", " })?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnexpectedToken { expected, found } => {
+                f.write_str("Expected ")?;
+                write_expected(f, expected)?;
+                match found.as_str() {
+                    "\n" | "\r\n" | "\r" => f.write_str(", found newline"),
+                    _ => write!(f, ", found '{}'", found),
+                }
+            }
+            ParseErrorKind::UnexpectedEof { expected } => {
+                f.write_str("Expected ")?;
+                write_expected(f, expected)?;
+                f.write_str(", found end of input")
+            }
+            ParseErrorKind::ReservedWord(keyword) => {
+                write!(f, "'{}' is a reserved word and cannot be used as an identifier", keyword)
+            }
+            ParseErrorKind::InvalidLiteral { literal, reason: LiteralError::OutOfRange(type_name) } => {
+                write!(f, "Literal '{}' is out of range for {}", literal, type_name)
+            }
+            ParseErrorKind::InvalidLiteral { literal, reason: LiteralError::Malformed } => {
+                write!(f, "Invalid literal '{}'", literal)
+            }
+            ParseErrorKind::InvalidPrecedence(precedence) => {
+                write!(f, "Precedence must be between 0 and 9, found {}", precedence)
+            }
+            ParseErrorKind::ReservedOperator(operator) => {
+                write!(f, "'{}' cannot be declared as an operator", operator)
+            }
+            ParseErrorKind::DuplicateFixity(operator) => {
+                write!(f, "'{}' already has a fixity declaration", operator)
+            }
+            ParseErrorKind::ChainedOperators { first, second } => {
+                write!(f, "'{}' cannot be chained with '{}' without parentheses", first, second)
+            }
+            ParseErrorKind::UnmatchedBrace => f.write_str("Unmatched '}'"),
+            ParseErrorKind::InvalidInput => f.write_str("Invalid input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Span,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, span: Span, line: usize, column: usize) -> Self {
+        ParseError { kind, span, line, column }
+    }
+
+    /// An error of `kind` at `token`.
+    pub(crate) fn at(kind: ParseErrorKind, token: &lexer::Token) -> Self {
+        ParseError::new(kind, token.span, token.line, token.column)
+    }
+
+    /// `token` found where one of `expected` should be, which is the end of
+    /// input if `token` is the `Eof` token. An `Error` token gives an
+    /// `InvalidInput` error rather than quoting its text.
+    pub(crate) fn unexpected(token: &lexer::Token, expected: Vec<Expected>) -> Self {
+        match token.kind {
+            TokenKind::Eof => return ParseError::at(ParseErrorKind::UnexpectedEof { expected }, token),
+            TokenKind::Error => return ParseError::at(ParseErrorKind::InvalidInput, token),
+            _ => {}
+        }
+        ParseError::at(ParseErrorKind::UnexpectedToken { expected, found: token.value.to_string() }, token)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "{}:{}: ", self.line, self.column)?;
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl Error for ParseError {}
+
+/// The error for `token` found where an identifier should be, calling out
+/// reserved words explicitly.
+pub(crate) fn expected_identifier(token: &lexer::Token) -> ParseError {
+    match token.kind {
+        TokenKind::Keyword(keyword) => ParseError::at(ParseErrorKind::ReservedWord(keyword), token),
+        _ => ParseError::unexpected(token, vec![Expected::Identifier]),
+    }
+}
+
+#[cfg(test)]
+mod test_error {
+    use super::*;
+
+    #[test]
+    fn lists_every_expected_alternative() {
+        let kind = ParseErrorKind::UnexpectedToken {
+            expected: vec![Expected::NewLine, Expected::Token(";"), Expected::Token("}")],
+            found: "b".to_string(),
+        };
+        assert_eq!(kind.to_string(), "Expected newline, ';' or '}', found 'b'");
+        let kind = ParseErrorKind::UnexpectedEof { expected: vec![Expected::Identifier, Expected::Token(")"), Expected::Token(",")] };
+        assert_eq!(kind.to_string(), "Expected identifier, ')' or ',', found end of input");
+    }
+
+    #[test]
+    fn displays_the_position_when_known() {
+        let error = ParseError::new(ParseErrorKind::ReservedWord(Keyword::Let), Span::default(), 2, 5);
+        assert_eq!(error.to_string(), "2:5: 'let' is a reserved word and cannot be used as an identifier");
+        let error = ParseError::new(ParseErrorKind::UnexpectedEof { expected: vec![Expected::Expression] }, Span::default(), 0, 0);
+        assert_eq!(error.to_string(), "Expected expression, found end of input");
+    }
+
+    #[test]
+    fn reports_the_eof_token_as_end_of_input() {
+        let (tokens, _) = lexer::tokenize_with_options("a ", &lexer::LexerOptions { lossless: true, ..Default::default() });
+        let error = ParseError::unexpected(&tokens[1], vec![Expected::Expression]);
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedEof { expected: vec![Expected::Expression] });
+        assert_eq!((error.line, error.column), (1, 3));
+    }
+
+    #[test]
+    fn reports_error_tokens_without_quoting_them() {
+        let (tokens, errors) = lexer::tokenize("'unterminated\nstring");
+        assert_eq!(errors.len(), 1);
+        let error = ParseError::unexpected(&tokens[0], vec![Expected::Token(")")]);
+        assert_eq!(error.to_string(), "1:1: Invalid input");
+    }
+}
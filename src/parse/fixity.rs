@@ -0,0 +1,288 @@
+// fixity_declaration:
+//     ('infixl' | 'infixr' | 'infix') integer operator '=' identifier
+
+use std::collections::HashMap;
+
+use crate::lexer::{Keyword, Span, Symbol, Token, TokenKind};
+
+use super::operator::BinaryOp;
+use super::{Expected, Parse, ParseError, ParseErrorKind, Spanned, TokenCursor};
+
+/// Operators the grammar itself uses, which cannot be given a fixity any
+/// more than the built-in binary operators can.
+const RESERVED_OPERATORS: &[&str] = &["=", "=>", ".."];
+
+/// The highest precedence an operator can be declared with. Higher
+/// precedences bind tighter.
+const MAX_PRECEDENCE: u8 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right,
+    /// The operator cannot be chained with itself or with another operator
+    /// of the same precedence without parentheses.
+    None,
+}
+
+/// How an operator groups, and the function it stands for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fixity {
+    pub associativity: Associativity,
+    pub precedence: u8,
+    pub function: Symbol,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixityDeclaration {
+    pub operator: Spanned<Symbol>,
+    pub fixity: Fixity,
+}
+
+impl FixityDeclaration {
+    pub fn new(operator: Spanned<Symbol>, fixity: Fixity) -> Self {
+        FixityDeclaration { operator, fixity }
+    }
+}
+
+impl Parse for FixityDeclaration {
+    fn parse(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError>
+        where
+            Self: Sized {
+        let token = tokens.peek();
+        let associativity = match token.kind {
+            TokenKind::Keyword(Keyword::Infixl) => Associativity::Left,
+            TokenKind::Keyword(Keyword::Infixr) => Associativity::Right,
+            TokenKind::Keyword(Keyword::Infix) => Associativity::None,
+            _ => {
+                return Err(ParseError::unexpected(token, vec![Expected::Token("infix"), Expected::Token("infixl"), Expected::Token("infixr")]));
+            }
+        };
+        let keyword = tokens.advance();
+
+        let token = tokens.peek();
+        if token.kind != TokenKind::Integer {
+            return Err(ParseError::unexpected(token, vec![Expected::Precedence]));
+        }
+        let precedence = match token.value.parse::<u8>() {
+            Ok(precedence) if precedence <= MAX_PRECEDENCE => precedence,
+            _ => {
+                return Err(ParseError::at(ParseErrorKind::InvalidPrecedence(token.value.to_string()), token));
+            }
+        };
+        tokens.advance();
+
+        let token = tokens.peek();
+        if token.kind != TokenKind::Operator {
+            return Err(ParseError::unexpected(token, vec![Expected::Operator]));
+        }
+        if RESERVED_OPERATORS.contains(&token.text) || BinaryOp::lookup(&token.value).is_some() || !is_declarable(&token.value) {
+            return Err(ParseError::at(ParseErrorKind::ReservedOperator(token.value.to_string()), token));
+        }
+        // the table holds the first declaration of each operator
+        if tokens.fixities().declared_at(&token.value).is_some_and(|first| first != token.span) {
+            return Err(ParseError::at(ParseErrorKind::DuplicateFixity(token.value.to_string()), &keyword));
+        }
+        let operator = Spanned::new(Symbol::intern(&token.value), token.span);
+        tokens.advance();
+
+        tokens.expect_operator("=")?;
+        let function = tokens.expect_identifier()?;
+
+        let span = keyword.span.to(function.span);
+        let declaration = FixityDeclaration::new(operator, Fixity { associativity, precedence, function: function.node });
+        Ok(Spanned::new(declaration, span))
+    }
+}
+
+/// Whether `operator` is made of operator characters rather than being
+/// punctuation such as `(` or `,`.
+fn is_declarable(operator: &str) -> bool {
+    !operator.chars().any(|c| matches!(c, '(' | ')' | '{' | '}' | '[' | ']' | ',' | ';'))
+}
+
+/// The fixity of every operator declared in a module, and where its
+/// declaration names it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FixityTable {
+    fixities: HashMap<String, (Fixity, Span)>,
+}
+
+impl FixityTable {
+    /// Collects the fixity declarations anywhere in `tokens`, so that an
+    /// operator can be used before the line that declares it. Only the
+    /// first declaration of an operator counts. Malformed and repeated
+    /// declarations are skipped here and reported when they are parsed.
+    pub fn collect(tokens: &[Token]) -> Self {
+        let mut table = FixityTable::default();
+        for (index, token) in tokens.iter().enumerate() {
+            if !matches!(token.kind, TokenKind::Keyword(Keyword::Infix | Keyword::Infixl | Keyword::Infixr)) {
+                continue;
+            }
+            // a declaration is five tokens long
+            let mut declaration = TokenCursor::without_fixities(tokens[index..tokens.len().min(index + 5)].to_vec());
+            if let Ok(Spanned { node: FixityDeclaration { operator, fixity }, .. }) = FixityDeclaration::parse(&mut declaration) {
+                table.fixities.entry(operator.node.to_string()).or_insert((fixity, operator.span));
+            }
+        }
+        table
+    }
+
+    /// Every declared operator.
+    pub fn operators(&self) -> impl Iterator<Item = &str> {
+        self.fixities.keys().map(String::as_str)
+    }
+
+    pub fn get(&self, operator: &str) -> Option<Fixity> {
+        self.fixities.get(operator).map(|(fixity, _)| *fixity)
+    }
+
+    /// Where the declaration that counts for `operator` names it.
+    pub fn declared_at(&self, operator: &str) -> Option<Span> {
+        self.fixities.get(operator).map(|(_, span)| *span)
+    }
+}
+
+#[cfg(test)]
+mod test_fixity {
+    use super::*;
+    use crate::lexer;
+    use crate::parse::{call, identifier, lexed, module::Module, Expression};
+
+    fn parse(input: &str) -> Result<Module, ParseError> {
+        Module::parse(&mut lexed(input)).map(|module| module.node)
+    }
+
+    #[test]
+    fn parses_a_declaration() {
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new("infixr 5 |>> = pipe"));
+        let declaration = FixityDeclaration::parse(&mut tokens).unwrap();
+        assert_eq!(declaration.node, FixityDeclaration::new("|>>".into(), Fixity {
+            associativity: Associativity::Right,
+            precedence: 5,
+            function: "pipe".into(),
+        }));
+        assert_eq!((declaration.span.start, declaration.span.end), (0, 19));
+        assert_eq!((declaration.operator.span.start, declaration.operator.span.end), (9, 12));
+    }
+
+    #[test]
+    fn returns_error_for_out_of_range_precedence() {
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new("infixl 10 <+> = combine"));
+        let error = FixityDeclaration::parse(&mut tokens).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidPrecedence("10".to_string()));
+        assert_eq!((error.line, error.column), (1, 8));
+    }
+
+    #[test]
+    fn returns_error_for_reserved_operators() {
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new("infixl 1 => = arrow"));
+        let error = FixityDeclaration::parse(&mut tokens).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::ReservedOperator("=>".to_string()));
+        assert_eq!((error.line, error.column), (1, 10));
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new("infixl 5 .. = range"));
+        let error = FixityDeclaration::parse(&mut tokens).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::ReservedOperator("..".to_string()));
+    }
+
+    #[test]
+    fn returns_error_for_built_in_operators() {
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new("infixl 6 + = add"));
+        assert_eq!(FixityDeclaration::parse(&mut tokens).unwrap_err().kind, ParseErrorKind::ReservedOperator("+".to_string()));
+    }
+
+    #[test]
+    fn places_declared_operators_among_built_in_ones() {
+        let module = parse("infixl 5 <+> = combine\na + b <+> c == d").unwrap();
+        let sum = Expression::Binary { op: BinaryOp::Add, lhs: Box::new(identifier("a")), rhs: Box::new(identifier("b")) };
+        let combined = call(identifier("combine"), vec![sum.into(), identifier("c")]);
+        assert_eq!(module.expressions[1].node, Expression::Binary { op: BinaryOp::Equal, lhs: Box::new(combined), rhs: Box::new(identifier("d")) });
+    }
+
+    #[test]
+    fn desugars_operators_to_calls() {
+        let module = parse("infixl 6 <+> = combine\na <+> b").unwrap();
+        assert_eq!(module.expressions[1], call(identifier("combine"), vec![identifier("a"), identifier("b")]));
+    }
+
+    #[test]
+    fn groups_by_associativity() {
+        let module = parse("infixl 6 <+> = add\ninfixr 5 |>> = pipe\na <+> b <+> c\na |>> b |>> c").unwrap();
+        let added = call(identifier("add"), vec![identifier("a"), identifier("b")]);
+        assert_eq!(module.expressions[2], call(identifier("add"), vec![added, identifier("c")]));
+        let piped = call(identifier("pipe"), vec![identifier("b"), identifier("c")]);
+        assert_eq!(module.expressions[3], call(identifier("pipe"), vec![identifier("a"), piped]));
+    }
+
+    #[test]
+    fn lexes_declared_operators_whole_above_their_declaration() {
+        let module = parse("a <&> b\ninfixl 6 <&> = both").unwrap();
+        assert_eq!(module.expressions[0], call(identifier("both"), vec![identifier("a"), identifier("b")]));
+    }
+
+    #[test]
+    fn parses_declarations_with_the_equals_sign_against_the_operator() {
+        let module = parse("infixl 6 <+>=add\na <+> b").unwrap();
+        assert_eq!(module.expressions[1], call(identifier("add"), vec![identifier("a"), identifier("b")]));
+    }
+
+    #[test]
+    fn binds_higher_precedence_tighter() {
+        let module = parse("a <+> b <*> c\ninfixl 6 <+> = add\ninfixl 7 <*> = mul").unwrap();
+        let product = call(identifier("mul"), vec![identifier("b"), identifier("c")]);
+        assert_eq!(module.expressions[0], call(identifier("add"), vec![identifier("a"), product]));
+    }
+
+    #[test]
+    fn returns_error_for_chained_non_associative_operators() {
+        let error = parse("infix 4 === = same\na === b === c").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::ChainedOperators { first: "===".to_string(), second: "===".to_string() });
+        assert_eq!((error.line, error.column), (2, 9));
+    }
+
+    #[test]
+    fn returns_error_for_mixed_associativity_at_one_precedence() {
+        let error = parse("infixl 6 <+> = add\ninfixr 6 <-> = sub\na <+> b <-> c").unwrap_err();
+        assert_eq!(error.to_string(), "3:9: '<+>' cannot be chained with '<->' without parentheses");
+        let error = parse("infixl 6 <+> = add\ninfixr 6 <-> = sub\na <-> b <+> c").unwrap_err();
+        assert_eq!(error.to_string(), "3:9: '<->' cannot be chained with '<+>' without parentheses");
+    }
+
+    #[test]
+    fn returns_error_for_chains_in_either_order() {
+        let error = parse("infixr 4 <=> = cmp\na == b <=> c").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::ChainedOperators { first: "==".to_string(), second: "<=>".to_string() });
+        let error = parse("infixr 4 <=> = cmp\na <=> b == c").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::ChainedOperators { first: "<=>".to_string(), second: "==".to_string() });
+        assert_eq!((error.line, error.column), (2, 9));
+        let error = parse("infixr 4 <=> = cmp\na <=> b + c == d").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::ChainedOperators { first: "<=>".to_string(), second: "==".to_string() });
+    }
+
+    #[test]
+    fn chains_right_associative_operators_across_tighter_ones() {
+        let module = parse("infixr 5 |>> = pipe\na |>> b <*> c |>> d\ninfixl 7 <*> = mul").unwrap();
+        let product = call(identifier("mul"), vec![identifier("b"), identifier("c")]);
+        let rest = call(identifier("pipe"), vec![product, identifier("d")]);
+        assert_eq!(module.expressions[1], call(identifier("pipe"), vec![identifier("a"), rest]));
+    }
+
+    #[test]
+    fn parses_expressions_with_the_fixities_of_their_own_tokens() {
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new("a <+> b\ninfixl 6 <+> = combine"));
+        assert_eq!(Expression::parse(&mut tokens).unwrap(), call(identifier("combine"), vec![identifier("a"), identifier("b")]));
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(TokenKind::Symbol, "a", 1, 1),
+            lexer::Token::new(TokenKind::Operator, "<+>", 3, 1),
+            lexer::Token::new(TokenKind::Symbol, "b", 7, 1),
+        ]);
+        assert_eq!(Expression::parse(&mut tokens).unwrap(), identifier("a"));
+    }
+
+    #[test]
+    fn returns_error_for_duplicate_declarations() {
+        let error = parse("infixl 6 <+> = add\ninfixl 7 <+> = mul").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::DuplicateFixity("<+>".to_string()));
+        assert_eq!((error.line, error.column), (2, 1));
+    }
+}
@@ -1,125 +1,106 @@
 // function_call:
-//     identifier '('(expression (, expression)*)?')'
+//     operand '('(expression (, expression)*)?')'
+//
+// After an error in an argument, parsing resumes at the next ',' or ')'
+// outside of brackets.
 
-use crate::lexer::{Token, TokenKind};
-
-use super::{Parse, Expression, ParseError};
+use super::{next_element, parse_element, Expression, ParseError, Spanned, TokenCursor};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct FunctionCall {
-    name: String,
-    args: Vec<Expression>,
+    callee: Box<Spanned<Expression>>,
+    args: Vec<Spanned<Expression>>,
 }
 
 impl FunctionCall {
-    fn new(name: String, args: Vec<Expression>) -> Self {
-        FunctionCall { name, args }
+    pub fn new(callee: Spanned<Expression>, args: Vec<Spanned<Expression>>) -> Self {
+        FunctionCall { callee: Box::new(callee), args }
     }
 }
 
-impl Parse for FunctionCall {
-    fn parse( tokens: &mut Vec<Token>) -> Result<Self, ParseError>
-        where
-            Self: Sized {
-        let name = match tokens.pop() {
-            Some(token) => {
-                if token.kind != crate::lexer::TokenKind::Symbol {
-                    return Err(ParseError::new("Expected identifier", token.line, token.column));
-                }
-                token.value
-            }
-            None => {
-                return Err(ParseError::new("Expected identifier", 0, 0));
-            }
-        };
-
-        match tokens.pop() {
-            Some(token) => {
-                if token.kind != crate::lexer::TokenKind::Operator || token.value != "(" {
-                    return Err(ParseError::new("Expected '('", token.line, token.column));
-                }
-            }
-            None => {
-                return Err(ParseError::new("Expected '('", 0, 0));
-            }
-        }
-
-        match tokens.last() {
-            Some(token) => {
-                if token.kind == TokenKind::Operator && token.value == ")" {
-                    tokens.pop();
-                    return Ok(FunctionCall::new(name, Vec::new()));
-                }
-
-                if token.kind == TokenKind::Operator && token.value == "," {
-                    return Err(ParseError::new("Expected expression", token.line, token.column));
-                }
+/// Parses the arguments of a call of `callee`, which can be any operand, so
+/// that `make_adder(1)(2)` and `((x) => x)(5)` are calls too. A bad
+/// argument is reported and skipped, leaving the others.
+pub(super) fn parse_call(tokens: &mut TokenCursor, callee: Spanned<Expression>) -> Result<Spanned<Expression>, ParseError> {
+    tokens.expect_operator("(")?;
 
-                let mut args = Vec::new();
-                // push the first argument
-                args.push(Expression::parse(tokens)?);
-                loop {
-                    match tokens.last() {
-                        Some(token) => {
-                            if token.kind == TokenKind::Operator && token.value == ")" {
-                                tokens.pop();
-                                break;
-                            }
-                        }
-                        None => {
-                            return Err(ParseError::new("Expected ')'", 0, 0));
-                        }
-                    }
-                    match tokens.pop() {
-                        Some(token) => {
-                            if token.kind != TokenKind::Operator || token.value != "," {
-                                println!("{:?}", token);
-                                return Err(ParseError::new("Expected ','", token.line, token.column));
-                            }
-                        }
-                        None => {
-                            return Err(ParseError::new("Expected ','", 0, 0));
-                        }
-                    }
-                    args.push(Expression::parse(tokens)?);
-                }
-                return Ok(FunctionCall::new(name, args));
-            }
-            None => {
-                return Err(ParseError::new("Expected ')'", 0, 0));
-            }
+    let mut args = Vec::new();
+    if !tokens.at_operator(")") {
+        args.push(parse_element(tokens, ")")?);
+        while next_element(tokens, ")")? {
+            args.push(parse_element(tokens, ")")?);
         }
     }
+    let span = callee.span.to(tokens.advance().span);
+    Ok(Spanned::new(Expression::FunctionCall(FunctionCall::new(callee, args)), span))
 }
 
 #[cfg(test)]
 mod test_function_call {
-    use crate::{lexer, parse::{Parse, Expression}};
+    use crate::lexer;
     use super::*;
+    use crate::parse::{call, identifier, parse_expression as parse};
 
     #[test]
     fn test_function_call_with_one_arg() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Symbol, "foo".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Operator, "(".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Symbol, "bar".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Operator, ")".to_string(), 0, 0),
-        ].into_iter().rev().collect();
-        let function_call = FunctionCall::parse(&mut tokens).unwrap();
-        assert_eq!(function_call, FunctionCall::new("foo".to_string(), vec![Expression::Identifier("bar".to_string())]));
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Symbol, "foo", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, "(", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Symbol, "bar", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, ")", 0, 0),
+        ]);
+        let function_call = Expression::parse(&mut tokens).unwrap();
+        assert_eq!(function_call, call(identifier("foo"), vec![identifier("bar")]));
     }
 
     #[test]
     fn test_function_call_with_two_args() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Symbol, "foo".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Operator, "(".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Symbol, "bar".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Operator, ",".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Symbol, "baz".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Operator, ")".to_string(), 0, 0),
-        ].into_iter().rev().collect();
-        let function_call = FunctionCall::parse(&mut tokens).unwrap();
-        assert_eq!(function_call, FunctionCall::new("foo".to_string(), vec![Expression::Identifier("bar".to_string()), Expression::Identifier("baz".to_string())]));
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Symbol, "foo", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, "(", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Symbol, "bar", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, ",", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Symbol, "baz", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, ")", 0, 0),
+        ]);
+        let function_call = Expression::parse(&mut tokens).unwrap();
+        assert_eq!(function_call, call(identifier("foo"), vec![identifier("bar"), identifier("baz")]));
+    }
+
+    #[test]
+    fn spans_from_callee_to_closing_paren() {
+        let function_call = parse("foo(bar, 1)").unwrap();
+        assert_eq!((function_call.span.start, function_call.span.end), (0, 11));
+        let Expression::FunctionCall(function_call) = function_call.node else {
+            panic!("expected a call");
+        };
+        assert_eq!((function_call.callee.span.start, function_call.callee.span.end), (0, 3));
+        assert_eq!((function_call.args[1].span.start, function_call.args[1].span.end), (9, 10));
+    }
+
+    #[test]
+    fn applies_calls_in_a_chain() {
+        let adder = call(identifier("make_adder"), vec![Expression::Integer(1.into()).into()]);
+        assert_eq!(parse("make_adder(1)(2)"), Ok(call(adder, vec![Expression::Integer(2.into()).into()])));
+    }
+
+    #[test]
+    fn calls_a_lambda_immediately() {
+        let called = parse("((x) => x)(5)").unwrap();
+        let Expression::FunctionCall(function_call) = called.node else {
+            panic!("expected a call");
+        };
+        assert!(matches!(function_call.callee.node, Expression::Lambda(_)));
+    }
+
+    #[test]
+    fn calls_a_tuple_field() {
+        let field = Expression::TupleIndex { tuple: Box::new(identifier("t")), index: 0 };
+        assert_eq!(parse("t.0(x)"), Ok(call(field.into(), vec![identifier("x")])));
+    }
+
+    #[test]
+    fn binds_calls_tighter_than_operators() {
+        assert!(matches!(parse("-f(x)").unwrap().node, Expression::Unary { .. }));
     }
 }
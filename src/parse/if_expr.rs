@@ -1,17 +1,17 @@
 //if:
-//  'if' expression expression ('else' expression)?
+//  'if' expression expression (newline? 'else' expression)?
 use crate::lexer;
-use super::{Expression, Parse, ParseError};
+use super::{Expression, Parse, ParseError, Spanned, TokenCursor};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct IfExpr {
-    condition: Box<Expression>,
-    then_expression: Box<Expression>,
-    else_expression: Box<Option<Expression>>
+    condition: Box<Spanned<Expression>>,
+    then_expression: Box<Spanned<Expression>>,
+    else_expression: Box<Option<Spanned<Expression>>>
 }
 
 impl IfExpr {
-    fn new(condition: Box<Expression>, then_expression: Box<Expression>, else_expression: Box<Option<Expression>>) -> Self {
+    fn new(condition: Box<Spanned<Expression>>, then_expression: Box<Spanned<Expression>>, else_expression: Box<Option<Spanned<Expression>>>) -> Self {
         IfExpr {
             condition,
             then_expression,
@@ -21,35 +21,29 @@ impl IfExpr {
 }
 
 impl Parse for IfExpr {
-    fn parse(tokens: &mut Vec<lexer::Token>) -> Result<Self, ParseError>
+    fn parse(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError>
         where
             Self: Sized {
-        match tokens.last() {
-            Some(token) => {
-                if token.kind != lexer::TokenKind::Symbol || token.value != "if" {
-                    return Err(ParseError::new("Expected 'if'", token.line, token.column));
-                }
-                tokens.pop();
-            }
-            None => {
-                return Err(ParseError::new("Expected 'if'", 0, 0));
-            }
-        }
+        let start = tokens.expect(lexer::TokenKind::Keyword(lexer::Keyword::If))?.span;
 
         let condition = Expression::parse(tokens)?;
         let then_expression = Expression::parse(tokens)?;
-        let else_expression = match tokens.last() {
-            Some(token) => {
-                if token.kind == lexer::TokenKind::Symbol && token.value == "else" {
-                    tokens.pop();
-                    Some(Expression::parse(tokens)?)
-                } else {
-                    None
-                }
+        // `else` may start the next line, where the line break before it
+        // would otherwise end the `if`
+        let else_on_next_line = tokens.peek().kind == lexer::TokenKind::NewLine
+            && tokens.peek_nth(1).kind == lexer::TokenKind::Keyword(lexer::Keyword::Else);
+        if else_on_next_line {
+            tokens.advance();
+        }
+        let else_expression = match tokens.peek().kind == lexer::TokenKind::Keyword(lexer::Keyword::Else) {
+            true => {
+                tokens.advance();
+                Some(Expression::parse(tokens)?)
             }
-            None => None,
+            false => None,
         };
-        Ok(IfExpr::new(Box::new(condition), Box::new(then_expression), Box::new(else_expression)))
+        let end = else_expression.as_ref().unwrap_or(&then_expression).span;
+        Ok(Spanned::new(IfExpr::new(Box::new(condition), Box::new(then_expression), Box::new(else_expression)), start.to(end)))
     }
 }
 
@@ -59,45 +53,55 @@ mod test_if_expr {
 
     #[test]
     fn returns_error_when_no_if() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Symbol, "else".to_string(), 0, 0),
-        ];
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Keyword(lexer::Keyword::Else), "else", 0, 0),
+        ]);
         let result = IfExpr::parse(&mut tokens);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn returns_error_when_no_condition() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Symbol, "if".to_string(), 0, 0),
-        ];
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Keyword(lexer::Keyword::If), "if", 0, 0),
+        ]);
         let result = IfExpr::parse(&mut tokens);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn returns_error_when_no_then_expression() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Symbol, "if".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Number, "1".to_string(), 0, 0),
-        ].into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Keyword(lexer::Keyword::If), "if", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Integer, "1", 0, 0),
+        ]);
         let result = IfExpr::parse(&mut tokens);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn parses_without_else() {
-        let mut tokens = vec![
-            lexer::Token::new(lexer::TokenKind::Symbol, "if".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Number, "1".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Number, "2".to_string(), 0, 0),
-        ].into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Keyword(lexer::Keyword::If), "if", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Integer, "1", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Integer, "2", 0, 0),
+        ]);
         let result = IfExpr::parse(&mut tokens);
-        assert_eq!(result.is_ok(), true);
-        assert_eq!(result.unwrap(), IfExpr::new(
-            Box::new(Expression::Number(1)),
-            Box::new(Expression::Number(2)),
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().node, IfExpr::new(
+            Box::new(Expression::Integer(1.into()).into()),
+            Box::new(Expression::Integer(2.into()).into()),
             Box::new(None),
         ));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parses_else_on_the_next_line() {
+        let (mut tokens, errors) = TokenCursor::lex(lexer::Lexer::new("if x {\n  1\n}\nelse {\n  2\n}"));
+        assert!(errors.is_empty());
+        let if_expr = IfExpr::parse(&mut tokens).unwrap();
+        assert!(if_expr.else_expression.is_some());
+        assert!(tokens.is_at_end());
+        assert_eq!((if_expr.span.start, if_expr.span.end), (0, 25));
+    }
+}
@@ -0,0 +1,110 @@
+// interpolation:
+//     quote (string_fragment | '${' expression '}')* quote
+
+use crate::lexer::{Symbol, TokenKind};
+
+use super::{Expected, Expression, Parse, ParseError, Spanned, TokenCursor};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Part {
+    Literal(Symbol),
+    Expression(Spanned<Expression>),
+}
+
+impl Parse for Part {
+    fn parse(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError>
+        where
+            Self: Sized {
+        let token = tokens.peek();
+        match token.kind {
+            TokenKind::StringFragment => {
+                let token = tokens.advance();
+                let symbol = token.symbol.unwrap_or_else(|| Symbol::intern(&token.value));
+                Ok(Spanned::new(Part::Literal(symbol), token.span))
+            }
+            TokenKind::HoleStart => {
+                let start = tokens.advance().span;
+                if tokens.peek().kind == TokenKind::HoleEnd {
+                    return Err(ParseError::unexpected(tokens.peek(), vec![Expected::Expression]));
+                }
+                let expression = Expression::parse(tokens)?;
+                let end = tokens.expect(TokenKind::HoleEnd)?.span;
+                Ok(Spanned::new(Part::Expression(expression), start.to(end)))
+            }
+            _ => Err(ParseError::unexpected(token, vec![Expected::StringText, Expected::Token("${")])),
+        }
+    }
+}
+
+/// Parses an interpolated string from its opening to its closing quote.
+pub fn parse_interpolation(tokens: &mut TokenCursor) -> Result<Spanned<Vec<Spanned<Part>>>, ParseError> {
+    let start = tokens.expect(TokenKind::InterpolationStart)?.span;
+
+    let mut parts = Vec::new();
+    loop {
+        if tokens.peek().kind == TokenKind::InterpolationEnd {
+            let end = tokens.advance().span;
+            return Ok(Spanned::new(parts, start.to(end)));
+        }
+        parts.push(Part::parse(tokens)?);
+    }
+}
+
+#[cfg(test)]
+mod test_interpolation {
+    use super::*;
+    use crate::parse::{block::Block, function_call::FunctionCall, lexed, ParseErrorKind};
+
+    fn parse(input: &str) -> Result<Vec<Spanned<Part>>, ParseError> {
+        parse_interpolation(&mut lexed(input)).map(|parts| parts.node)
+    }
+
+    fn literal(text: &str) -> Spanned<Part> {
+        Part::Literal(text.into()).into()
+    }
+
+    fn hole(expression: Expression) -> Spanned<Part> {
+        Part::Expression(expression.into()).into()
+    }
+
+    #[test]
+    fn parses_literals_and_holes() {
+        assert_eq!(parse("'Hello ${name}, you are ${age}'"), Ok(vec![
+            literal("Hello "),
+            hole(Expression::Identifier("name".into())),
+            literal(", you are "),
+            hole(Expression::Identifier("age".into())),
+        ]));
+    }
+
+    #[test]
+    fn parses_nested_braces_and_quotes_in_holes() {
+        assert_eq!(parse("'${ {1} } and ${f('${x}')}'"), Ok(vec![
+            hole(Expression::Block(Block::new(vec![Expression::Integer(1.into()).into()]))),
+            literal(" and "),
+            hole(Expression::FunctionCall(FunctionCall::new(
+                Expression::Identifier("f".into()).into(),
+                vec![Expression::Interpolation(vec![hole(Expression::Identifier("x".into()))]).into()],
+            ))),
+        ]));
+    }
+
+    #[test]
+    fn spans_holes_from_dollar_to_brace() {
+        let parts = parse("'a ${b} c'").unwrap();
+        let spans: Vec<(u32, u32)> = parts.iter().map(|part| (part.span.start, part.span.end)).collect();
+        assert_eq!(spans, vec![(1, 3), (3, 7), (7, 9)]);
+    }
+
+    #[test]
+    fn returns_error_for_empty_hole() {
+        let error = parse("'a ${}'").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedToken { expected: vec![Expected::Expression], found: "}".to_string() });
+        assert_eq!((error.line, error.column), (1, 6));
+    }
+
+    #[test]
+    fn returns_error_for_more_than_one_expression_in_hole() {
+        assert!(parse("'${a b}'").is_err());
+    }
+}
@@ -1,147 +1,176 @@
 // lambda:
-//     '('identifier*')''=>' expression
+//     '('identifier*')''=>' expression | '=>' expression
 
-use std::string;
+use crate::lexer::{Symbol, TokenKind};
 
-use crate::lexer::{self, TokenKind};
-
-use super::{Expression, Parse, ParseError};
+use super::{Expected, Expression, Parse, ParseError, Spanned, TokenCursor};
 
 #[derive(Debug, Clone , PartialEq)]
 pub struct Lambda {
-    args: Vec<string::String>,
-    expression: Box<Expression>
+    args: Vec<Spanned<Symbol>>,
+    expression: Box<Spanned<Expression>>
 }
 
 impl Lambda {
-    fn new(args: Vec<string::String>, expression: Box<Expression>) -> Self {
-        return Lambda { args, expression};
+    pub(super) fn new(args: Vec<Spanned<Symbol>>, expression: Box<Spanned<Expression>>) -> Self {
+        Lambda { args, expression}
     }
 }
 
 impl Parse for Lambda {
-    fn parse( tokens: &mut Vec<crate::lexer::Token>) -> Result<Self, super::ParseError>
+    fn parse(tokens: &mut TokenCursor) -> Result<Spanned<Self>, super::ParseError>
         where
             Self: Sized {
 
-                match tokens.pop() {
-                    Some(token) => {
-                        if token.kind != lexer::TokenKind::Operator || token.value != "(" {
-                            return Err(ParseError::new("Expected '('", token.line, token.column));
-                        }
-                    } None => {
-                        return Err(ParseError::new("Expected token but found none", 0, 0));
-                    }
+                // `=> expression` is a lambda without parameters
+                if tokens.at_operator("=>") {
+                    let start = tokens.advance().span;
+                    let expression = Expression::parse(tokens)?;
+                    let span = start.to(expression.span);
+                    return Ok(Spanned::new(Lambda::new(Vec::new(), Box::new(expression)), span));
                 }
 
+                let start = tokens.expect_operator("(")?.span;
+
                 let mut args = Vec::new();
-                loop {
-                    match tokens.last() {
-                        Some(token) => {
-                            if token.kind == TokenKind::Operator && token.value == ")" {
-                                tokens.pop();
-                                break;
-                            }
-                        }
-                        None => {
-                            return Err(ParseError::new("Expected ')'", 0, 0));
-                        }
-                    }
-                    match tokens.pop() {
-                        Some(token) => {
-                            if token.kind != TokenKind::Symbol {
-                                return Err(ParseError::new("Expected identifier", token.line, token.column));
-                            }
-                            args.push(token.value);
-                        }
-                        None => {
-                            return Err(ParseError::new("Expected identifier", 0, 0));
-                        }
-                    }
-                }
-                
-                match tokens.pop() {
-                    Some(token) => {
-                        if token.kind != TokenKind::Operator || token.value != "=>" {
-                            return Err(ParseError::new("Expected '=>'", token.line, token.column));
-                        }
-                    }
-                    None => {
-                        return Err(ParseError::new("Expected '=>'", 0, 0));
+                while !tokens.at_operator(")") {
+                    if tokens.is_at_end() {
+                        return Err(ParseError::unexpected(tokens.peek(), vec![Expected::Token(")")]));
                     }
+                    args.push(tokens.expect_identifier()?);
                 }
+                tokens.advance();
+
+                tokens.expect_operator("=>")?;
 
                 let expression = Expression::parse(tokens)?;
 
-                return Ok(Lambda::new(args, Box::new(expression)));
+                let span = start.to(expression.span);
+                Ok(Spanned::new(Lambda::new(args, Box::new(expression)), span))
+    }
+}
+
+/// Whether the `(` next in `tokens` opens the parameters of a lambda: the
+/// `)` that matches it, however far ahead, is followed by `=>`.
+pub(super) fn starts_lambda(tokens: &TokenCursor) -> bool {
+    let mut depth = 0;
+    let mut n = 0;
+    loop {
+        let token = tokens.peek_nth(n);
+        if token.kind == TokenKind::Eof {
+            return false;
+        }
+        if token.kind == TokenKind::Operator {
+            match token.text {
+                "(" => depth += 1,
+                ")" => depth -= 1,
+                _ => {}
+            }
+        }
+        if depth == 0 {
+            let next = tokens.peek_nth(n + 1);
+            return next.kind == TokenKind::Operator && next.value == "=>";
+        }
+        n += 1;
     }
 }
 
 #[cfg(test)]
 mod test_lambda {
-    use crate::{lexer, parse::{Parse, Expression}};
+    use crate::{lexer, parse::{Expected, Expression, Parse, ParseErrorKind, TokenCursor}};
 
     #[test]
     fn test_lambda_returns_error_when_no_open_paren() {
-        let mut tokens = vec!(
-            lexer::Token::new(lexer::TokenKind::Operator, ")".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Symbol, "x".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Operator, "=>".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Number, "1".to_string(), 0, 0),
-        ).into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Operator, ")", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Symbol, "x", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, "=>", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Integer, "1", 0, 0),
+        ]);
         let lambda = super::Lambda::parse(&mut tokens);
-        assert_eq!(lambda, Err(super::ParseError::new("Expected '('", 0, 0)));
+        assert_eq!(lambda.unwrap_err().kind, ParseErrorKind::UnexpectedToken { expected: vec![Expected::Token("(")], found: ")".to_string() });
     }
 
     #[test]
     fn test_lambda_returns_error_when_no_close_paren() {
-        let mut tokens = vec!(
-            lexer::Token::new(lexer::TokenKind::Operator, "(".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Symbol, "x".to_string(), 0, 0),
-        ).into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Operator, "(", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Symbol, "x", 0, 0),
+        ]);
         let lambda = super::Lambda::parse(&mut tokens);
-        assert_eq!(lambda, Err(super::ParseError::new("Expected ')'", 0, 0)));
+        assert_eq!(lambda.unwrap_err().kind, ParseErrorKind::UnexpectedEof { expected: vec![Expected::Token(")")] });
     }
 
     #[test]
     fn test_lambda_returns_error_when_no_arrow() {
-        let mut tokens = vec!(
-            lexer::Token::new(lexer::TokenKind::Operator, "(".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Symbol, "x".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Operator, ")".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Number, "1".to_string(), 0, 0),
-        ).into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Operator, "(", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Symbol, "x", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, ")", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Integer, "1", 0, 0),
+        ]);
         let lambda = super::Lambda::parse(&mut tokens);
-        assert_eq!(lambda, Err(super::ParseError::new("Expected '=>'", 0, 0)));
+        assert_eq!(lambda.unwrap_err().kind, ParseErrorKind::UnexpectedToken { expected: vec![Expected::Token("=>")], found: "1".to_string() });
+    }
+
+    #[test]
+    fn test_lambda_returns_error_when_argument_is_reserved() {
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Operator, "(", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Keyword(lexer::Keyword::If), "if", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, ")", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, "=>", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Integer, "1", 0, 0),
+        ]);
+        let lambda = super::Lambda::parse(&mut tokens);
+        assert_eq!(lambda.unwrap_err().kind, ParseErrorKind::ReservedWord(lexer::Keyword::If));
     }
 
     #[test]
     fn test_lambda_with_one_argument() {
-        let mut tokens = vec!(
-            lexer::Token::new(lexer::TokenKind::Operator, "(".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Symbol, "x".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Operator, ")".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Operator, "=>".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Number, "1".to_string(), 0, 0),
-        ).into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Operator, "(", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Symbol, "x", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, ")", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, "=>", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Integer, "1", 0, 0),
+        ]);
         let lambda = super::Lambda::parse(&mut tokens);
         assert!(lambda.is_ok());
-        assert_eq!(lambda.unwrap(), super::Lambda::new(vec!("x".to_string()), Box::new(Expression::Number(1))));
+        assert_eq!(lambda.unwrap().node, super::Lambda::new(vec!("x".into()), Box::new(Expression::Integer(1.into()).into())));
     }
 
     #[test]
     fn test_lambda_with_two_arguments() {
-        let mut tokens = vec!(
-            lexer::Token::new(lexer::TokenKind::Operator, "(".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Symbol, "x".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Symbol, "y".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Operator, ")".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Operator, "=>".to_string(), 0, 0),
-            lexer::Token::new(lexer::TokenKind::Number, "1".to_string(), 0, 0),
-        ).into_iter().rev().collect();
+        let mut tokens = TokenCursor::new(vec![
+            lexer::Token::new(lexer::TokenKind::Operator, "(", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Symbol, "x", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Symbol, "y", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, ")", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Operator, "=>", 0, 0),
+            lexer::Token::new(lexer::TokenKind::Integer, "1", 0, 0),
+        ]);
 
         let lambda = super::Lambda::parse(&mut tokens);
         assert!(lambda.is_ok());
-        assert_eq!(lambda.unwrap(), super::Lambda::new(vec!("x".to_string(), "y".to_string()), Box::new(Expression::Number(1))));
+        assert_eq!(lambda.unwrap().node, super::Lambda::new(vec!("x".into(), "y".into()), Box::new(Expression::Integer(1.into()).into())));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parses_an_arrow_without_parameters() {
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new("=> 1"));
+        let lambda = super::Lambda::parse(&mut tokens).unwrap();
+        assert_eq!(lambda.node, super::Lambda::new(vec![], Box::new(Expression::Integer(1.into()).into())));
+        assert_eq!((lambda.span.start, lambda.span.end), (0, 4));
+    }
+
+    #[test]
+    fn looks_past_the_parameters_for_an_arrow() {
+        let lexed = |input| TokenCursor::lex(lexer::Lexer::new(input)).0;
+        assert!(super::starts_lambda(&lexed("(a b) => a")));
+        assert!(super::starts_lambda(&lexed("() => 1")));
+        assert!(!super::starts_lambda(&lexed("(a + (b)) * c")));
+        assert!(!super::starts_lambda(&lexed("(a, b)")));
+        assert!(!super::starts_lambda(&lexed("(a b")));
+    }
+}
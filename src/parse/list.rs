@@ -0,0 +1,163 @@
+// list:
+//     '[' (element (',' element)* ','?)? ']'
+//
+// element:
+//     '..' expression | expression
+//
+// After an error in an element, parsing resumes at the next ',' or ']'
+// outside of brackets.
+//
+// subscript:
+//     '[' expression ']' | '[' expression? '..' expression? ']'
+
+use super::{next_element, parse_element, Expected, Expression, Parse, ParseError, Spanned, TokenCursor};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element {
+    Item(Spanned<Expression>),
+    /// `..xs`, which inserts every element of `xs`.
+    Spread(Box<Spanned<Expression>>),
+}
+
+impl Parse for Element {
+    /// Parses an element of a list, recovering from a bad expression in it
+    /// as `parse_element` does.
+    fn parse(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError>
+        where
+            Self: Sized {
+        if tokens.at_operator("..") {
+            let start = tokens.advance().span;
+            let expression = parse_element(tokens, "]")?;
+            let span = start.to(expression.span);
+            return Ok(Spanned::new(Element::Spread(Box::new(expression)), span));
+        }
+        let expression = parse_element(tokens, "]")?;
+        let span = expression.span;
+        Ok(Spanned::new(Element::Item(expression), span))
+    }
+}
+
+/// Parses a list literal from its opening to its closing bracket. A bad
+/// element is reported and skipped, leaving the others.
+pub fn parse_list(tokens: &mut TokenCursor) -> Result<Spanned<Vec<Spanned<Element>>>, ParseError> {
+    let start = tokens.expect_operator("[")?.span;
+
+    let mut elements = Vec::new();
+    while !tokens.at_operator("]") {
+        elements.push(Element::parse(tokens)?);
+        if !next_element(tokens, "]")? {
+            break;
+        }
+    }
+    Ok(Spanned::new(elements, start.to(tokens.advance().span)))
+}
+
+/// Parses the index or slice in brackets after `collection`. Either bound
+/// of a slice can be left out, as in `xs[1..]` or `xs[..]`.
+pub(super) fn parse_index(tokens: &mut TokenCursor, collection: Spanned<Expression>) -> Result<Spanned<Expression>, ParseError> {
+    tokens.expect_operator("[")?;
+
+    let start = match tokens.at_operator("..") {
+        true => None,
+        false => Some(Box::new(Expression::parse(tokens)?)),
+    };
+    if !tokens.at_operator("..") {
+        let Some(index) = start else {
+            return Err(ParseError::unexpected(tokens.peek(), vec![Expected::Expression]));
+        };
+        let end = tokens.expect_operator("]").map_err(|error| match tokens.is_at_end() {
+            true => error,
+            false => ParseError::unexpected(tokens.peek(), vec![Expected::Token("]"), Expected::Token("..")]),
+        })?;
+        let span = collection.span.to(end.span);
+        return Ok(Spanned::new(Expression::Index { collection: Box::new(collection), index }, span));
+    }
+
+    tokens.advance();
+    let end = match tokens.at_operator("]") {
+        true => None,
+        false => Some(Box::new(Expression::parse(tokens)?)),
+    };
+    let span = collection.span.to(tokens.expect_operator("]")?.span);
+    Ok(Spanned::new(Expression::Slice { collection: Box::new(collection), start, end }, span))
+}
+
+#[cfg(test)]
+mod test_list {
+    use super::*;
+    use crate::parse::operator::UnaryOp;
+    use crate::parse::{identifier, integer, parse_expression as parse, ParseErrorKind};
+
+    fn items(values: &[u64]) -> Vec<Spanned<Element>> {
+        values.iter().map(|value| Element::Item(integer(*value)).into()).collect()
+    }
+
+    fn slice(start: Option<Spanned<Expression>>, end: Option<Spanned<Expression>>) -> Spanned<Expression> {
+        Expression::Slice { collection: Box::new(identifier("xs")), start: start.map(Box::new), end: end.map(Box::new) }.into()
+    }
+
+    #[test]
+    fn parses_list_literals() {
+        assert_eq!(parse("[]"), Ok(Expression::List(vec![]).into()));
+        assert_eq!(parse("[1, 2, 3]"), Ok(Expression::List(items(&[1, 2, 3])).into()));
+        assert_eq!(parse("[1, 2, 3,]"), Ok(Expression::List(items(&[1, 2, 3])).into()));
+        assert_eq!(parse("[\n  1,\n  2\n]"), Ok(Expression::List(items(&[1, 2])).into()));
+    }
+
+    #[test]
+    fn spreads_lists_into_a_literal() {
+        let mut elements = vec![Spanned::from(Element::Spread(Box::new(identifier("xs"))))];
+        elements.extend(items(&[4]));
+        assert_eq!(parse("[..xs, 4]"), Ok(Expression::List(elements).into()));
+    }
+
+    #[test]
+    fn indexes_lists() {
+        let index = Expression::Index { collection: Box::new(identifier("xs")), index: Box::new(identifier("i")) };
+        assert_eq!(parse("xs[i]"), Ok(index.clone().into()));
+        let nested = Expression::Index { collection: Box::new(index.into()), index: Box::new(integer(0)) };
+        assert_eq!(parse("xs[i][0]"), Ok(nested.into()));
+    }
+
+    #[test]
+    fn slices_lists() {
+        assert_eq!(parse("xs[1..3]"), Ok(slice(Some(integer(1)), Some(integer(3)))));
+        assert_eq!(parse("xs[1..]"), Ok(slice(Some(integer(1)), None)));
+        assert_eq!(parse("xs[..3]"), Ok(slice(None, Some(integer(3)))));
+        assert_eq!(parse("xs[..]"), Ok(slice(None, None)));
+        let last = Expression::Unary { op: UnaryOp::Negate, operand: Box::new(integer(1)) };
+        assert_eq!(parse("xs[1..-1]"), Ok(slice(Some(integer(1)), Some(last.into()))));
+    }
+
+    #[test]
+    fn spans_from_collection_to_bracket() {
+        let index = parse("xs[1..3]").unwrap();
+        assert_eq!((index.span.start, index.span.end), (0, 8));
+        let list = parse("[1, ..xs]").unwrap();
+        assert_eq!((list.span.start, list.span.end), (0, 9));
+        let Expression::List(elements) = list.node else {
+            panic!("expected a list");
+        };
+        assert_eq!((elements[1].span.start, elements[1].span.end), (4, 8));
+        let Element::Item(item) = &elements[0].node else {
+            panic!("expected an item");
+        };
+        assert_eq!((item.span.start, item.span.end), (1, 2));
+    }
+
+    #[test]
+    fn returns_error_for_missing_separator() {
+        let error = parse("[1 2]").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedToken {
+            expected: vec![Expected::Token(","), Expected::Token("]")],
+            found: "2".to_string(),
+        });
+        assert_eq!((error.line, error.column), (1, 4));
+    }
+
+    #[test]
+    fn returns_error_for_empty_index() {
+        let error = parse("xs[]").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedToken { expected: vec![Expected::Expression], found: "]".to_string() });
+    }
+}
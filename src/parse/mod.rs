@@ -1,34 +1,247 @@
-use crate::lexer;
+use std::ops::Deref;
+
+use crate::lexer::{self, Span};
+mod cursor;
 mod definition;
+mod error;
+mod fixity;
 mod block;
 mod if_expr;
 mod lambda;
 mod function_call;
+mod interpolation;
+mod list;
+mod number;
+mod operator;
+mod tuple;
 pub mod module;
 
-pub fn parse_module(tokens: &mut Vec<lexer::Token>) -> Result<module::Module, ParseError> {
-    module::Module::parse(tokens)
+pub use cursor::{Checkpoint, TokenCursor};
+pub use error::{Expected, LiteralError, ParseError, ParseErrorKind};
+
+use error::expected_identifier;
+
+/// Parses a module from the tokens of `lexer`, recovering from errors so
+/// that the module is built even from broken input, with an
+/// `Expression::Error` for each expression that could not be parsed. Every
+/// parse error is returned alongside, and so is every lexer error. The
+/// `Error` token for each lexer error parses as an `Expression::Error`
+/// without a parse error of its own.
+pub fn parse_module(lexer: lexer::Lexer) -> (Spanned<module::Module>, Vec<ParseError>, Vec<lexer::LexerError>) {
+    let (mut tokens, errors) = TokenCursor::lex(lexer);
+    let (module, parse_errors) = module::Module::parse_with_recovery(&mut tokens);
+    (module, parse_errors, errors)
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct ParseError {
-    message: String,
-    line: usize,
-    column: usize,
-}
-
-impl ParseError {
-    fn new(message: &str, line: usize, column: usize) -> Self {
-        ParseError {
-            message: message.to_string(),
-            line,
-            column,
+/// Whether `token` ends an expression in a sequence: a `NewLine` token or
+/// an explicit `;`.
+fn is_terminator(token: &lexer::Token) -> bool {
+    token.kind == lexer::TokenKind::NewLine || (token.kind == lexer::TokenKind::Operator && token.value == ";")
+}
+
+/// Whether `token` is the operator `text`.
+fn is_operator(token: &lexer::Token, text: &str) -> bool {
+    token.kind == lexer::TokenKind::Operator && token.text == text
+}
+
+fn is_closing(token: &lexer::Token) -> bool {
+    [")", "]", "}"].iter().any(|text| is_operator(token, text))
+}
+
+/// How many brackets are open after `token`, with `depth` open before it.
+fn nesting(depth: usize, token: &lexer::Token) -> usize {
+    if ["(", "[", "{"].iter().any(|text| is_operator(token, text)) {
+        depth + 1
+    } else if is_closing(token) {
+        depth.saturating_sub(1)
+    } else {
+        depth
+    }
+}
+
+/// Whether a new expression in a module or block can start at `token`
+/// after an error: at a terminator, `let` or the `}` closing the block.
+fn resumes_statement(token: &lexer::Token) -> bool {
+    is_terminator(token) || is_operator(token, "}") || token.kind == lexer::TokenKind::Keyword(lexer::Keyword::Let)
+}
+
+/// Whether a comma-separated sequence can carry on at `token` after an
+/// error: at a `,` or a closing bracket.
+fn resumes_element(token: &lexer::Token) -> bool {
+    is_operator(token, ",") || is_closing(token)
+}
+
+/// Skips tokens after a parse error in an expression started at `from`, up
+/// to the next token that `resumes` the expression's sequence outside of
+/// the brackets the expression opened, or up to the `Eof` token. A closing
+/// bracket with no opening one is skipped unless it resumes the sequence.
+fn synchronize(tokens: &mut TokenCursor, from: Checkpoint, resumes: fn(&lexer::Token) -> bool) {
+    // the error is inside every bracket opened and not yet closed since
+    // the expression started
+    let mut depth = tokens.since(from).iter().fold(0, nesting);
+    loop {
+        let token = tokens.peek();
+        if token.kind == lexer::TokenKind::Eof || (depth == 0 && resumes(token)) {
+            return;
         }
+        depth = nesting(depth, token);
+        tokens.advance();
     }
 }
 
+/// An `Expression::Error` standing in for the tokens moved past since
+/// `from`, or empty at the next token if there are none.
+fn error_node(tokens: &TokenCursor, from: Checkpoint) -> Spanned<Expression> {
+    let span = match tokens.since(from) {
+        [first, .., last] => first.span.to(last.span),
+        [only] => only.span,
+        [] => {
+            let next = tokens.peek().span;
+            Span::new(next.file, next.start, next.start)
+        }
+    };
+    Spanned::new(Expression::Error, span)
+}
+
+/// Parses an element of a comma-separated sequence closed by `close`. An
+/// element that fails to parse is reported and skipped, with an
+/// `Expression::Error` standing in for it, as long as the sequence carries
+/// on after it with a `,` or `close`; otherwise the error is returned.
+fn parse_element(tokens: &mut TokenCursor, close: &str) -> Result<Spanned<Expression>, ParseError> {
+    let checkpoint = tokens.checkpoint();
+    Expression::parse(tokens).or_else(|error| {
+        synchronize(tokens, checkpoint, resumes_element);
+        if !tokens.at_operator(",") && !tokens.at_operator(close) {
+            return Err(error);
+        }
+        tokens.report(error);
+        Ok(error_node(tokens, checkpoint))
+    })
+}
+
+/// Moves past the `,` after an element of a sequence closed by `close`,
+/// returning whether another element can follow. Anything else before the
+/// next `,` or `close` is reported and skipped; if neither follows, the
+/// error is returned.
+fn next_element(tokens: &mut TokenCursor, close: &'static str) -> Result<bool, ParseError> {
+    if !tokens.at_operator(",") && !tokens.at_operator(close) {
+        let error = ParseError::unexpected(tokens.peek(), vec![Expected::Token(","), Expected::Token(close)]);
+        synchronize(tokens, tokens.checkpoint(), resumes_element);
+        if !tokens.at_operator(",") && !tokens.at_operator(close) {
+            return Err(error);
+        }
+        tokens.report(error);
+    }
+    match tokens.at_operator(",") {
+        true => {
+            tokens.advance();
+            Ok(true)
+        }
+        false => Ok(false),
+    }
+}
+
+/// A cursor over `input`, which must lex without errors, for tests.
+#[cfg(test)]
+fn lexed(input: &str) -> TokenCursor<'_> {
+    let (tokens, errors) = TokenCursor::lex(lexer::Lexer::new(input));
+    assert!(errors.is_empty(), "{:?}", errors);
+    tokens
+}
+
+/// Parses all of `input` with `parse`, for tests, failing with the first
+/// error whether it was recovered from or not.
+#[cfg(test)]
+fn parse_all<T>(input: &str, parse: fn(&mut TokenCursor) -> Result<T, ParseError>) -> Result<T, ParseError> {
+    let mut tokens = lexed(input);
+    let node = parse(&mut tokens);
+    if let Some(error) = tokens.take_errors().into_iter().next() {
+        return Err(error);
+    }
+    let node = node?;
+    assert!(tokens.is_at_end());
+    Ok(node)
+}
+
+/// Parses all of `input` as one expression, for tests.
+#[cfg(test)]
+fn parse_expression(input: &str) -> Result<Spanned<Expression>, ParseError> {
+    parse_all(input, Expression::parse)
+}
+
+/// An identifier, for comparing with parsed trees in tests.
+#[cfg(test)]
+fn identifier(name: &str) -> Spanned<Expression> {
+    Expression::Identifier(name.into()).into()
+}
+
+/// An integer without a suffix, for comparing with parsed trees in tests.
+#[cfg(test)]
+fn integer(value: u64) -> Spanned<Expression> {
+    Expression::Integer(value.into()).into()
+}
+
+/// A call of `callee` with `args`, for comparing with parsed trees in tests.
+#[cfg(test)]
+fn call(callee: Spanned<Expression>, args: Vec<Spanned<Expression>>) -> Spanned<Expression> {
+    Expression::FunctionCall(function_call::FunctionCall::new(callee, args)).into()
+}
+
+/// A node together with the source range it was parsed from. Equality
+/// ignores the span, so trees compare by structure alone.
+#[derive(Debug, Clone)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Spanned { node, span }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned::new(f(self.node), self.span)
+    }
+}
+
+impl<T: PartialEq> PartialEq for Spanned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.node
+    }
+}
+
+/// A node with an empty span, for building trees by hand.
+impl<T> From<T> for Spanned<T> {
+    fn from(node: T) -> Self {
+        Spanned::new(node, Span::default())
+    }
+}
+
+impl From<&str> for Spanned<lexer::Symbol> {
+    fn from(text: &str) -> Self {
+        Spanned::from(lexer::Symbol::from(text))
+    }
+}
+
+/// The symbol of an identifier or string token, spanning the token.
+fn spanned_symbol(token: &lexer::Token) -> Spanned<lexer::Symbol> {
+    Spanned::new(token.symbol.unwrap_or_else(|| lexer::Symbol::intern(&token.value)), token.span)
+}
+
 trait Parse {
-    fn parse( tokens: &mut Vec<lexer::Token>) -> Result<Self, ParseError>
+    /// Parses a node from the front of `tokens`, with the span from its
+    /// first token to its last. Errors the parse recovers from are reported
+    /// to `tokens` rather than returned.
+    fn parse(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError>
     where
         Self: Sized;
 }
@@ -36,113 +249,144 @@ trait Parse {
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
     Definition(definition::Definition),
-    Number(i32),
+    Integer(number::Integer),
+    Float(number::Float),
     Block(block::Block),
     Lambda(lambda::Lambda),
     IfExpr(if_expr::IfExpr),
-    Identifier(String),
+    Identifier(lexer::Symbol),
     FunctionCall(function_call::FunctionCall),
-    StringLiteral(String),
+    StringLiteral(lexer::Symbol),
+    Interpolation(Vec<Spanned<interpolation::Part>>),
+    Boolean(bool),
+    FixityDeclaration(fixity::FixityDeclaration),
+    /// Stands in for an expression that failed to parse, so that the rest
+    /// of the module can still be built.
+    Error,
+    Binary {
+        op: operator::BinaryOp,
+        lhs: Box<Spanned<Expression>>,
+        rhs: Box<Spanned<Expression>>,
+    },
+    Unary {
+        op: operator::UnaryOp,
+        operand: Box<Spanned<Expression>>,
+    },
+    Tuple(Vec<Spanned<Expression>>),
+    TupleIndex {
+        tuple: Box<Spanned<Expression>>,
+        index: usize,
+    },
+    List(Vec<Spanned<list::Element>>),
+    Index {
+        collection: Box<Spanned<Expression>>,
+        index: Box<Spanned<Expression>>,
+    },
+    /// `xs[start..end]`, where either bound can be left out.
+    Slice {
+        collection: Box<Spanned<Expression>>,
+        start: Option<Box<Spanned<Expression>>>,
+        end: Option<Box<Spanned<Expression>>>,
+    },
 }
 
 impl Expression {
-    pub fn parse(tokens: &mut Vec<lexer::Token>) -> Result<Self, ParseError> {
-        match tokens.last() {
-            Some(token) => {
-                match token.kind {
-                    lexer::TokenKind::Number => {
-                        let value = match tokens.pop() {
-                            Some(token) => {
-                                match token.value.parse::<i32>() {
-                                    Ok(value) => value,
-                                    Err(_) => {
-                                        return Err(ParseError::new("Expected number", token.line, token.column));
-                                    }
-                                }
-                            }
-                            None => {
-                                return Err(ParseError::new("Expected number", 0, 0));
-                            }
-                        };
-                        return Ok(Expression::Number(value));
+    pub fn parse(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError> {
+        operator::parse_binary(tokens)
+    }
+
+    /// Parses a primary expression and any tuple indices, calls and
+    /// subscripts after it, applied from left to right.
+    fn parse_operand(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError> {
+        let mut expression = Expression::parse_primary(tokens)?;
+        loop {
+            if tuple::at_index(tokens) {
+                expression = tuple::parse_index(tokens, expression)?;
+            } else if tokens.at_operator("(") {
+                expression = function_call::parse_call(tokens, expression)?;
+            } else if tokens.at_operator("[") {
+                expression = list::parse_index(tokens, expression)?;
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    /// Whether a tuple index, call or subscript follows.
+    fn at_postfix(tokens: &TokenCursor) -> bool {
+        tuple::at_index(tokens) || tokens.at_operator("(") || tokens.at_operator("[")
+    }
+
+    fn parse_primary(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError> {
+        let token = tokens.peek();
+        match token.kind {
+            lexer::TokenKind::Integer => {
+                Ok(number::Integer::parse(tokens)?.map(Expression::Integer))
+            }
+            lexer::TokenKind::Float => {
+                Ok(number::Float::parse(tokens)?.map(Expression::Float))
+            }
+            lexer::TokenKind::Operator => {
+                match token.value.as_ref() {
+                    "{" => {
+                        Ok(block::Block::parse(tokens)?.map(Expression::Block))
+                    }
+                    "(" if lambda::starts_lambda(tokens) => {
+                        Ok(lambda::Lambda::parse(tokens)?.map(Expression::Lambda))
+                    }
+                    "(" => {
+                        tuple::parse_parenthesized(tokens)
+                    }
+                    "=>" => {
+                        Ok(lambda::Lambda::parse(tokens)?.map(Expression::Lambda))
+                    }
+                    "[" => {
+                        Ok(list::parse_list(tokens)?.map(Expression::List))
                     }
-                    lexer::TokenKind::Operator => {
-                        match token.value.as_str() {
-                            "{" => {
-                                return Ok(Expression::Block(block::Block::parse(tokens)?));
-                            }
-                            "(" => {
-                                return Ok(Expression::Lambda(lambda::Lambda::parse(tokens)?));
-                            }
-                            _ => {
-                                return Err(ParseError::new("Unknown Operator", token.line, token.column));
-                            }
-                        }
+                    "-" | "!" => {
+                        operator::parse_unary(tokens)
                     }
-                    lexer::TokenKind::Symbol => {
-                        match token.value.as_str() {
-                            "let" => {
-                                return Ok(Expression::Definition(definition::Definition::parse(tokens)?));
-                            }
-                            "if" => {
-                                return Ok(Expression::IfExpr(if_expr::IfExpr::parse(tokens)?));
-                            }
-                            _ => {
-                                if tokens.len() < 2 {
-                                    match tokens.pop() {
-                                        Some(token) => {
-                                            return Ok(Expression::Identifier(token.value.clone()));
-                                        }
-                                        None => {
-                                            return Err(ParseError::new("Unknown Parser Error", 0, 0));
-                                        }
-                                    }
-                                }
-
-                                match tokens[tokens.len() - 2].kind {
-                                    lexer::TokenKind::Operator => {
-                                        match tokens[tokens.len() - 2].value.as_str() {
-                                            "(" => {
-                                                return Ok(Expression::FunctionCall(function_call::FunctionCall::parse(tokens)?));
-                                            }
-                                            _ => {
-                                                match tokens.pop() {
-                                                    Some(token) => {
-                                                        return Ok(Expression::Identifier(token.value.clone()));
-                                                    }
-                                                    None => {
-                                                        return Err(ParseError::new("Unknown Parser Error", 0, 0));
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                    _ => {
-                                        return Ok(Expression::Identifier(token.value.clone()));
-                                    }
-                                }
-                            }
-                        }
+                    _ => {
+                        Err(ParseError::unexpected(token, vec![Expected::Expression]))
+                    }
+                }
+            }
+            lexer::TokenKind::Keyword(keyword) => {
+                match keyword {
+                    lexer::Keyword::Let => {
+                        Ok(definition::Definition::parse(tokens)?.map(Expression::Definition))
+                    }
+                    lexer::Keyword::If => {
+                        Ok(if_expr::IfExpr::parse(tokens)?.map(Expression::IfExpr))
+                    }
+                    lexer::Keyword::Infix | lexer::Keyword::Infixl | lexer::Keyword::Infixr => {
+                        Ok(fixity::FixityDeclaration::parse(tokens)?.map(Expression::FixityDeclaration))
                     }
-                    lexer::TokenKind::StringLiteral => {
-                        let value = match tokens.pop() {
-                            Some(token) => {
-                                token.value
-                            }
-                            None => {
-                                return Err(ParseError::new("Expected string literal", 0, 0));
-                            }
-                        };
-                        return Ok(Expression::StringLiteral(value));
+                    lexer::Keyword::True | lexer::Keyword::False => {
+                        let span = tokens.advance().span;
+                        Ok(Spanned::new(Expression::Boolean(keyword == lexer::Keyword::True), span))
                     }
                     _ => {
-                        return Err(ParseError::new("Unknown Token", token.line, token.column));
+                        Err(ParseError::at(ParseErrorKind::ReservedWord(keyword), token))
                     }
                 }
             }
-            None => {
-                return Err(ParseError::new("Unknown Parser Error", 0, 0));
+            lexer::TokenKind::Symbol => {
+                Ok(spanned_symbol(&tokens.advance()).map(Expression::Identifier))
+            }
+            lexer::TokenKind::StringLiteral => {
+                Ok(spanned_symbol(&tokens.advance()).map(Expression::StringLiteral))
+            }
+            lexer::TokenKind::InterpolationStart => {
+                Ok(interpolation::parse_interpolation(tokens)?.map(Expression::Interpolation))
+            }
+            // the lexer has already reported what is wrong with the token
+            lexer::TokenKind::Error => {
+                Ok(Spanned::new(Expression::Error, tokens.advance().span))
+            }
+            _ => {
+                Err(ParseError::unexpected(token, vec![Expected::Expression]))
             }
         }
     }
-}
\ No newline at end of file
+}
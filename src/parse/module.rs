@@ -1,36 +1,287 @@
-// module: expression+ EOF
+// module:
+//  terminator* expression (terminator+ expression)* terminator* EOF
+//
+// After an error, parsing resumes at the next newline, ';' or 'let' outside
+// of brackets. A '}' outside of any block is reported and skipped.
 
-use super::{Expression, Parse};
+use crate::lexer::TokenKind;
+
+use super::{error_node, is_terminator, resumes_statement, synchronize, Expected, Expression, Parse, ParseError, ParseErrorKind, Spanned, TokenCursor};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Module {
-    pub expressions: Vec<Expression>
+    pub expressions: Vec<Spanned<Expression>>
 }
 
 impl Module {
-    pub fn new(expressions: Vec<Expression>) -> Self {
+    pub fn new(expressions: Vec<Spanned<Expression>>) -> Self {
         Module { expressions }
     }
 }
 
-impl Parse for Module {
-    fn parse( tokens: &mut Vec<crate::lexer::Token>) -> Result<Self, super::ParseError>
-        where
-            Self: Sized {
+impl Module {
+    /// Parses a module, recovering from each error at the next newline,
+    /// `;` or `let` outside of brackets, and inside blocks and brackets
+    /// wherever they can carry on. The module is always built, with an
+    /// `Expression::Error` standing in for every expression that failed to
+    /// parse, and returned along with every error found. Errors at an
+    /// `Error` token are left out, as the lexer has reported those.
+    pub fn parse_with_recovery(tokens: &mut TokenCursor) -> (Spanned<Self>, Vec<ParseError>) {
         let mut expressions = Vec::new();
-        loop {
-            match tokens.last() {
-                Some(_token ) => {
-                    expressions.push(Expression::parse(tokens)?);
-                }
-                None => {
-                    break;
+        tokens.skip_terminators();
+        while !tokens.is_at_end() {
+            if tokens.at_operator("}") {
+                let token = tokens.advance();
+                tokens.report(ParseError::at(ParseErrorKind::UnmatchedBrace, &token));
+                tokens.skip_terminators();
+                continue;
+            }
+            let checkpoint = tokens.checkpoint();
+            match Expression::parse(tokens) {
+                Ok(expression) => expressions.push(expression),
+                Err(error) => {
+                    tokens.report(error);
+                    synchronize(tokens, checkpoint, resumes_statement);
+                    expressions.push(error_node(tokens, checkpoint));
+                    tokens.skip_terminators();
+                    continue;
                 }
             }
+            let token = tokens.peek();
+            if is_terminator(token) {
+                tokens.skip_terminators();
+            } else if token.kind != TokenKind::Eof && !tokens.at_operator("}") {
+                tokens.report(ParseError::unexpected(token, vec![Expected::NewLine, Expected::Token(";")]));
+                synchronize(tokens, tokens.checkpoint(), resumes_statement);
+                tokens.skip_terminators();
+            }
         }
-        if expressions.len() == 0 {
-            return Err(super::ParseError::new("Expected expression", 0, 0));
+        if expressions.is_empty() {
+            tokens.report(ParseError::unexpected(tokens.peek(), vec![Expected::Expression]));
+            return (Spanned::from(Module::new(expressions)), tokens.take_errors());
+        }
+        // the module runs from its first expression to its last
+        let span = expressions[0].span.to(expressions[expressions.len() - 1].span);
+        (Spanned::new(Module::new(expressions), span), tokens.take_errors())
+    }
+}
+
+impl Parse for Module {
+    /// Parses a module, failing with the first error found.
+    fn parse(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError>
+        where
+            Self: Sized {
+        let (module, errors) = Module::parse_with_recovery(tokens);
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(module),
         }
-        Ok(Module::new(expressions))
+    }
+}
+
+#[cfg(test)]
+mod test_module {
+    use super::*;
+    use crate::lexer;
+    use crate::parse::block::Block;
+    use crate::parse::definition::Definition;
+    use crate::parse::lambda::Lambda;
+    use crate::parse::list::Element;
+    use crate::parse::{call, identifier, integer, lexed};
+
+    fn parse(input: &str) -> Result<Module, ParseError> {
+        Module::parse(&mut lexed(input)).map(|module| module.node)
+    }
+
+    fn definition(identifier: &str, expression: Expression) -> Spanned<Expression> {
+        Expression::Definition(Definition::new(identifier.into(), Box::new(expression.into()))).into()
+    }
+
+    #[test]
+    fn ends_definitions_at_newlines() {
+        let module = parse("// leading comment\n\nlet x = f\n(a) => a\n");
+        let expressions = module.unwrap().expressions;
+        assert_eq!(expressions.len(), 2);
+        assert_eq!(expressions[0], definition("x", Expression::Identifier("f".into())));
+        assert!(matches!(expressions[1].node, Expression::Lambda(_)));
+    }
+
+    #[test]
+    fn separates_expressions_on_one_line_by_semicolons() {
+        assert_eq!(parse("let x = 1; let y = 2;"), Ok(Module::new(vec![
+            definition("x", Expression::Integer(1.into())),
+            definition("y", Expression::Integer(2.into())),
+        ])));
+    }
+
+    #[test]
+    fn continues_calls_across_lines_inside_parentheses() {
+        let module = parse("f(\n  1,\n  2\n)\ng()").unwrap();
+        assert_eq!(module.expressions.len(), 2);
+    }
+
+    #[test]
+    fn records_the_span_of_each_expression() {
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new("let x = 1\n  f(a)\n"));
+        let module = Module::parse(&mut tokens).unwrap();
+        let spans: Vec<(u32, u32)> = module.expressions.iter().map(|expression| (expression.span.start, expression.span.end)).collect();
+        assert_eq!(spans, vec![(0, 9), (12, 16)]);
+        assert_eq!((module.span.start, module.span.end), (0, 16));
+    }
+
+    #[test]
+    fn returns_error_for_expressions_on_one_line() {
+        let error = parse("let x = 1 let y = 2").unwrap_err();
+        assert_eq!(error.to_string(), "1:11: Expected newline or ';', found 'let'");
+    }
+
+    #[test]
+    fn returns_error_for_reserved_words_in_expressions() {
+        let error = parse("let x = type").unwrap_err();
+        assert_eq!(error.to_string(), "1:9: 'type' is a reserved word and cannot be used as an identifier");
+        let error = parse("print(match)").unwrap_err();
+        assert_eq!((error.kind, error.line, error.column), (ParseErrorKind::ReservedWord(lexer::Keyword::Match), 1, 7));
+    }
+
+    #[test]
+    fn returns_error_for_empty_module() {
+        let (module, errors, _) = crate::parse::parse_module(lexer::Lexer::new("\n;\n"));
+        assert!(module.expressions.is_empty());
+        assert_eq!(errors[0].kind, ParseErrorKind::UnexpectedEof { expected: vec![Expected::Expression] });
+        assert_eq!((errors[0].line, errors[0].column), (3, 1));
+    }
+
+    fn recover(input: &str) -> (Vec<Expression>, Vec<ParseError>) {
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new(input));
+        let (module, errors) = Module::parse_with_recovery(&mut tokens);
+        (module.node.expressions.into_iter().map(|expression| expression.node).collect(), errors)
+    }
+
+    #[test]
+    fn recovers_at_the_next_line() {
+        let (expressions, errors) = recover("let = 1\nlet y = 2\n)\nz");
+        assert_eq!(expressions, vec![
+            Expression::Error,
+            definition("y", Expression::Integer(2.into())).node,
+            Expression::Error,
+            Expression::Identifier("z".into()),
+        ]);
+        let errors: Vec<(ParseErrorKind, usize, usize)> = errors.into_iter().map(|error| (error.kind, error.line, error.column)).collect();
+        assert_eq!(errors, vec![
+            (ParseErrorKind::UnexpectedToken { expected: vec![Expected::Identifier], found: "=".to_string() }, 1, 5),
+            (ParseErrorKind::UnexpectedToken { expected: vec![Expected::Expression], found: ")".to_string() }, 3, 1),
+        ]);
+    }
+
+    #[test]
+    fn recovers_at_let_on_the_same_line() {
+        let (expressions, errors) = recover("let x = 1 let y = 2");
+        assert_eq!(expressions, vec![
+            definition("x", Expression::Integer(1.into())).node,
+            definition("y", Expression::Integer(2.into())).node,
+        ]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ParseErrorKind::UnexpectedToken {
+            expected: vec![Expected::NewLine, Expected::Token(";")],
+            found: "let".to_string(),
+        });
+    }
+
+    #[test]
+    fn recovers_inside_a_block() {
+        let (expressions, errors) = recover("let f = {\n  a\n  let = 2\n  b\n}\nlet g = 3");
+        let block = Block::new(vec![identifier("a"), Expression::Error.into(), identifier("b")]);
+        assert_eq!(expressions, vec![
+            definition("f", Expression::Block(block)).node,
+            definition("g", Expression::Integer(3.into())).node,
+        ]);
+        let errors: Vec<(ParseErrorKind, usize, usize)> = errors.into_iter().map(|error| (error.kind, error.line, error.column)).collect();
+        assert_eq!(errors, vec![(ParseErrorKind::UnexpectedToken { expected: vec![Expected::Identifier], found: "=".to_string() }, 3, 7)]);
+    }
+
+    #[test]
+    fn reports_every_error_in_a_block() {
+        let (expressions, errors) = recover("let main = => {\n  let = 1\n  let = 2\n  ok\n}");
+        let block = Block::new(vec![Expression::Error.into(), Expression::Error.into(), identifier("ok")]);
+        let main = Lambda::new(Vec::new(), Box::new(Expression::Block(block).into()));
+        assert_eq!(expressions, vec![definition("main", Expression::Lambda(main)).node]);
+        let errors: Vec<(usize, usize)> = errors.into_iter().map(|error| (error.line, error.column)).collect();
+        assert_eq!(errors, vec![(2, 7), (3, 7)]);
+    }
+
+    #[test]
+    fn recovers_inside_brackets() {
+        let (expressions, errors) = recover("f(a b, c)\n[1, let, 3]\n(x, =, z)");
+        assert_eq!(expressions, vec![
+            call(identifier("f"), vec![identifier("a"), identifier("c")]).node,
+            Expression::List(vec![
+                Element::Item(integer(1)).into(),
+                Element::Item(Expression::Error.into()).into(),
+                Element::Item(integer(3)).into(),
+            ]),
+            Expression::Tuple(vec![identifier("x"), Expression::Error.into(), identifier("z")]),
+        ]);
+        let errors: Vec<(usize, usize)> = errors.into_iter().map(|error| (error.line, error.column)).collect();
+        assert_eq!(errors, vec![(1, 5), (2, 8), (3, 5)]);
+    }
+
+    #[test]
+    fn recovers_before_an_unmatched_brace_further_on() {
+        let (expressions, errors) = recover("let = 1\nlet y = 2\nlet z = 3\n}\nlet w = 4");
+        assert_eq!(expressions, vec![
+            Expression::Error,
+            definition("y", Expression::Integer(2.into())).node,
+            definition("z", Expression::Integer(3.into())).node,
+            definition("w", Expression::Integer(4.into())).node,
+        ]);
+        let errors: Vec<(ParseErrorKind, usize, usize)> = errors.into_iter().map(|error| (error.kind, error.line, error.column)).collect();
+        assert_eq!(errors, vec![
+            (ParseErrorKind::UnexpectedToken { expected: vec![Expected::Identifier], found: "=".to_string() }, 1, 5),
+            (ParseErrorKind::UnmatchedBrace, 4, 1),
+        ]);
+    }
+
+    #[test]
+    fn returns_error_for_an_unmatched_brace_after_an_expression() {
+        let (expressions, errors) = recover("let x = 1 }\nlet y = { 2 } }");
+        assert_eq!(expressions.len(), 2);
+        let errors: Vec<(ParseErrorKind, usize, usize)> = errors.into_iter().map(|error| (error.kind, error.line, error.column)).collect();
+        assert_eq!(errors, vec![(ParseErrorKind::UnmatchedBrace, 1, 11), (ParseErrorKind::UnmatchedBrace, 2, 15)]);
+    }
+
+    #[test]
+    fn spans_the_skipped_tokens_with_the_error_node() {
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new("let = 1 2\nx"));
+        let (module, _) = Module::parse_with_recovery(&mut tokens);
+        let span = module.expressions[0].span;
+        assert_eq!((span.start, span.end), (0, 9));
+    }
+
+    #[test]
+    fn parses_the_shipped_example() {
+        let (module, errors, lexer_errors) = crate::parse::parse_module(lexer::Lexer::new(include_str!("../../example.trk")));
+        assert!(errors.is_empty() && lexer_errors.is_empty());
+        assert!(matches!(&module.expressions[..], [
+            Spanned { node: Expression::Definition(_), .. },
+            Spanned { node: Expression::Definition(_), .. },
+        ]));
+    }
+
+    #[test]
+    fn collects_lexer_and_parser_errors_together() {
+        let (module, errors, lexer_errors) = crate::parse::parse_module(lexer::Lexer::new("let a = #\nlet b = 1 @; (c"));
+        assert_eq!(module.expressions.len(), 3);
+        assert_eq!(module.expressions[0].node, definition("a", Expression::Error).node);
+        let errors: Vec<(ParseErrorKind, usize, usize)> = errors.into_iter().map(|error| (error.kind, error.line, error.column)).collect();
+        assert_eq!(errors, vec![(ParseErrorKind::UnexpectedEof { expected: vec![Expected::Token(","), Expected::Token(")")] }, 2, 16)]);
+        assert_eq!(lexer_errors.len(), 2);
+    }
+
+    #[test]
+    fn leaves_unterminated_strings_to_the_lexer_error() {
+        let (module, errors, lexer_errors) = crate::parse::parse_module(lexer::Lexer::new("let s = 'abc\nlet t = 1"));
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(lexer_errors.len(), 1);
+        assert_eq!(module.expressions[0].node, definition("s", Expression::Error).node);
     }
 }
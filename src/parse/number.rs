@@ -0,0 +1,173 @@
+// integer:
+//     number without '.' or exponent, optionally suffixed i8..i64 / u8..u64
+// float:
+//     number with '.' or exponent, optionally suffixed f32 / f64
+
+use crate::lexer::{self, FloatSuffix, IntegerSuffix};
+
+use super::{Expected, LiteralError, Parse, ParseError, ParseErrorKind, Spanned, TokenCursor};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Integer {
+    pub value: u64,
+    pub suffix: Option<IntegerSuffix>,
+}
+
+impl Integer {
+    pub fn new(value: u64, suffix: Option<IntegerSuffix>) -> Self {
+        Integer { value, suffix }
+    }
+}
+
+impl From<u64> for Integer {
+    fn from(value: u64) -> Self {
+        Integer::new(value, None)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Float {
+    pub value: f64,
+    pub suffix: Option<FloatSuffix>,
+}
+
+impl Float {
+    pub fn new(value: f64, suffix: Option<FloatSuffix>) -> Self {
+        Float { value, suffix }
+    }
+}
+
+impl From<f64> for Float {
+    fn from(value: f64) -> Self {
+        Float::new(value, None)
+    }
+}
+
+impl Integer {
+    /// Parses an integer literal with a `-` right before it, which can be
+    /// one past the maximum of a signed type, as in `-128i8`.
+    pub(super) fn parse_negated(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError> {
+        Integer::parse_within(tokens, IntegerSuffix::max_negated)
+    }
+
+    /// Parses an integer literal no greater than `max` of its type.
+    fn parse_within(tokens: &mut TokenCursor, max: fn(&IntegerSuffix) -> u64) -> Result<Spanned<Self>, ParseError> {
+        let token = tokens.peek();
+        if token.kind != lexer::TokenKind::Integer {
+            return Err(ParseError::unexpected(token, vec![Expected::Integer]));
+        }
+        let token = tokens.advance();
+
+        // unsuffixed integers are i64
+        let (radix, digits, suffix) = lexer::split_integer(&token.value);
+        let max = max(&suffix.unwrap_or(IntegerSuffix::I64));
+        match u64::from_str_radix(&digits, radix) {
+            Ok(value) if value <= max => Ok(Spanned::new(Integer::new(value, suffix), token.span)),
+            _ => Err(out_of_range(&token, suffix.unwrap_or(IntegerSuffix::I64).as_str())),
+        }
+    }
+}
+
+impl Parse for Integer {
+    fn parse(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError>
+        where
+            Self: Sized {
+        Integer::parse_within(tokens, IntegerSuffix::max_value)
+    }
+}
+
+impl Parse for Float {
+    fn parse(tokens: &mut TokenCursor) -> Result<Spanned<Self>, ParseError>
+        where
+            Self: Sized {
+        let token = tokens.peek();
+        if token.kind != lexer::TokenKind::Float {
+            return Err(ParseError::unexpected(token, vec![Expected::Float]));
+        }
+        let token = tokens.advance();
+
+        let (digits, suffix) = lexer::split_float(&token.value);
+        let value = match digits.parse::<f64>() {
+            Ok(value) => value,
+            Err(_) => {
+                let kind = ParseErrorKind::InvalidLiteral { literal: token.value.to_string(), reason: LiteralError::Malformed };
+                return Err(ParseError::at(kind, &token));
+            }
+        };
+        let in_range = match suffix {
+            Some(FloatSuffix::F32) => (value as f32).is_finite(),
+            _ => value.is_finite(),
+        };
+        if !in_range {
+            return Err(out_of_range(&token, suffix.unwrap_or(FloatSuffix::F64).as_str()));
+        }
+        Ok(Spanned::new(Float::new(value, suffix), token.span))
+    }
+}
+
+fn out_of_range(token: &lexer::Token, type_name: &'static str) -> ParseError {
+    let kind = ParseErrorKind::InvalidLiteral { literal: token.value.to_string(), reason: LiteralError::OutOfRange(type_name) };
+    ParseError::at(kind, token)
+}
+
+#[cfg(test)]
+mod test_number {
+    use super::*;
+
+    fn integer(text: &str) -> Result<Integer, ParseError> {
+        let mut tokens = TokenCursor::new(vec![lexer::Token::new(lexer::TokenKind::Integer, text, 3, 2)]);
+        Integer::parse(&mut tokens).map(|integer| integer.node)
+    }
+
+    fn out_of_range(literal: &str, type_name: &'static str) -> ParseError {
+        let kind = ParseErrorKind::InvalidLiteral { literal: literal.to_string(), reason: LiteralError::OutOfRange(type_name) };
+        ParseError::new(kind, lexer::Span::default(), 2, 3)
+    }
+
+    fn float(text: &str) -> Result<Float, ParseError> {
+        let mut tokens = TokenCursor::new(vec![lexer::Token::new(lexer::TokenKind::Float, text, 3, 2)]);
+        Float::parse(&mut tokens).map(|float| float.node)
+    }
+
+    #[test]
+    fn parses_integers_in_every_radix() {
+        assert_eq!(integer("1_000"), Ok(Integer::from(1000)));
+        assert_eq!(integer("0xff"), Ok(Integer::from(255)));
+        assert_eq!(integer("0o17"), Ok(Integer::from(15)));
+        assert_eq!(integer("0b1010"), Ok(Integer::from(10)));
+        assert_eq!(integer("0xffu8"), Ok(Integer::new(255, Some(IntegerSuffix::U8))));
+    }
+
+    #[test]
+    fn returns_error_for_integer_out_of_range() {
+        assert_eq!(integer("9223372036854775808"), Err(out_of_range("9223372036854775808", "i64")));
+        assert_eq!(integer("256u8"), Err(out_of_range("256u8", "u8")));
+        assert_eq!(integer("99999999999999999999u64"), Err(out_of_range("99999999999999999999u64", "u64")));
+        assert_eq!(integer("18446744073709551615u64"), Ok(Integer::new(u64::MAX, Some(IntegerSuffix::U64))));
+    }
+
+    #[test]
+    fn parses_the_magnitude_of_the_most_negative_integers() {
+        let negated = |text| {
+            let mut tokens = TokenCursor::new(vec![lexer::Token::new(lexer::TokenKind::Integer, text, 3, 2)]);
+            Integer::parse_negated(&mut tokens).map(|integer| integer.node)
+        };
+        assert_eq!(negated("128i8"), Ok(Integer::new(128, Some(IntegerSuffix::I8))));
+        assert_eq!(negated("9223372036854775808"), Ok(Integer::from(1 << 63)));
+        assert_eq!(negated("129i8"), Err(out_of_range("129i8", "i8")));
+        assert_eq!(negated("256u8"), Err(out_of_range("256u8", "u8")));
+    }
+
+    #[test]
+    fn parses_floats() {
+        assert_eq!(float("1.5"), Ok(Float::from(1.5)));
+        assert_eq!(float("2.5e-3"), Ok(Float::from(0.0025)));
+        assert_eq!(float("1_000.5f32"), Ok(Float::new(1000.5, Some(FloatSuffix::F32))));
+    }
+
+    #[test]
+    fn returns_error_for_float_out_of_range() {
+        assert_eq!(float("1e999"), Err(out_of_range("1e999", "f64")));
+        assert_eq!(float("1e39f32"), Err(out_of_range("1e39f32", "f32")));
+    }
+}
@@ -0,0 +1,347 @@
+// expression:
+//     unary (operator unary)*
+//
+// unary:
+//     ('-' | '!') unary | operand
+
+use std::fmt;
+
+use crate::lexer::{Symbol, Token, TokenKind};
+
+use super::fixity::{Associativity, FixityTable};
+use super::{function_call::FunctionCall, number, Expected, Expression, ParseError, ParseErrorKind, Spanned, TokenCursor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+impl BinaryOp {
+    pub const ALL: [BinaryOp; 13] = [
+        BinaryOp::Or,
+        BinaryOp::And,
+        BinaryOp::Equal,
+        BinaryOp::NotEqual,
+        BinaryOp::Less,
+        BinaryOp::LessEqual,
+        BinaryOp::Greater,
+        BinaryOp::GreaterEqual,
+        BinaryOp::Add,
+        BinaryOp::Subtract,
+        BinaryOp::Multiply,
+        BinaryOp::Divide,
+        BinaryOp::Remainder,
+    ];
+
+    pub fn lookup(operator: &str) -> Option<BinaryOp> {
+        BinaryOp::ALL.iter().copied().find(|op| op.as_str() == operator)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinaryOp::Or => "||",
+            BinaryOp::And => "&&",
+            BinaryOp::Equal => "==",
+            BinaryOp::NotEqual => "!=",
+            BinaryOp::Less => "<",
+            BinaryOp::LessEqual => "<=",
+            BinaryOp::Greater => ">",
+            BinaryOp::GreaterEqual => ">=",
+            BinaryOp::Add => "+",
+            BinaryOp::Subtract => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Remainder => "%",
+        }
+    }
+
+    /// How the operator groups, on the same 0 to 9 scale as declared
+    /// operators, so `infixl 5 <+> = f` sits between `+` and `==`.
+    pub fn fixity(&self) -> (Associativity, u8) {
+        match self {
+            BinaryOp::Or => (Associativity::Right, 2),
+            BinaryOp::And => (Associativity::Right, 3),
+            BinaryOp::Equal
+            | BinaryOp::NotEqual
+            | BinaryOp::Less
+            | BinaryOp::LessEqual
+            | BinaryOp::Greater
+            | BinaryOp::GreaterEqual => (Associativity::None, 4),
+            BinaryOp::Add | BinaryOp::Subtract => (Associativity::Left, 6),
+            BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Remainder => (Associativity::Left, 7),
+        }
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Negate,
+    Not,
+}
+
+impl UnaryOp {
+    pub fn lookup(operator: &str) -> Option<UnaryOp> {
+        match operator {
+            "-" => Some(UnaryOp::Negate),
+            "!" => Some(UnaryOp::Not),
+            _ => None,
+        }
+    }
+}
+
+/// An operator between two operands: built in, or declared by the module
+/// and standing for a call of `function`.
+enum Infix {
+    Builtin(BinaryOp),
+    Declared { function: Symbol },
+}
+
+/// The operator `token` is between two operands, with its associativity
+/// and precedence, looking declared operators up in `fixities`.
+fn infix_of(token: &Token, fixities: &FixityTable) -> Option<(Infix, Associativity, u8)> {
+    if token.kind != TokenKind::Operator {
+        return None;
+    }
+    if let Some(op) = BinaryOp::lookup(&token.value) {
+        let (associativity, precedence) = op.fixity();
+        return Some((Infix::Builtin(op), associativity, precedence));
+    }
+    fixities.get(&token.value).map(|fixity| {
+        (Infix::Declared { function: fixity.function }, fixity.associativity, fixity.precedence)
+    })
+}
+
+/// An operator that has been parsed, with how it groups.
+struct Operator<'src> {
+    token: Token<'src>,
+    associativity: Associativity,
+    precedence: u8,
+}
+
+impl Operator<'_> {
+    /// The loosest precedence of the operators in its right operand.
+    fn rhs_precedence(&self) -> u8 {
+        match self.associativity {
+            Associativity::Right => self.precedence,
+            Associativity::Left | Associativity::None => self.precedence + 1,
+        }
+    }
+
+    /// Whether it cannot be followed by an operator of `associativity` and
+    /// `precedence` without parentheses, with only operators that bind
+    /// tighter between the two.
+    fn chains_with(&self, associativity: Associativity, precedence: u8) -> bool {
+        self.precedence == precedence && (self.associativity != associativity || associativity == Associativity::None)
+    }
+}
+
+/// Parses an operand followed by any operators that belong in the right
+/// operand of `enclosing`, or by every operator at the top level.
+fn parse_binary_within<'src>(tokens: &mut TokenCursor<'src>, enclosing: Option<&Operator<'src>>) -> Result<Spanned<Expression>, ParseError> {
+    let min_precedence = enclosing.map_or(0, Operator::rhs_precedence);
+    let mut lhs = Expression::parse_operand(tokens)?;
+    let mut previous: Option<Operator> = None;
+    while let Some((infix, associativity, precedence)) = infix_of(tokens.peek(), tokens.fixities()) {
+        if precedence < min_precedence {
+            break;
+        }
+        let token = tokens.advance();
+        // the operator before this one at the same precedence is either an
+        // earlier one at this level or, in `a <=> b == c`, the one whose
+        // right operand this is
+        let left = previous
+            .as_ref()
+            .filter(|previous| previous.precedence == precedence)
+            .or(enclosing.filter(|enclosing| enclosing.precedence == precedence));
+        if let Some(left) = left.filter(|left| left.chains_with(associativity, precedence)) {
+            let kind = ParseErrorKind::ChainedOperators {
+                first: left.token.value.to_string(),
+                second: token.value.to_string(),
+            };
+            return Err(ParseError::at(kind, &token));
+        }
+        let operator = Operator { token, associativity, precedence };
+        let rhs = parse_binary_within(tokens, Some(&operator))?;
+        let span = lhs.span.to(rhs.span);
+        let expression = match infix {
+            Infix::Builtin(op) => Expression::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) },
+            Infix::Declared { function } => {
+                // the call is of the function but located at the operator
+                let callee = Spanned::new(Expression::Identifier(function), operator.token.span);
+                Expression::FunctionCall(FunctionCall::new(callee, vec![lhs, rhs]))
+            }
+        };
+        lhs = Spanned::new(expression, span);
+        previous = Some(operator);
+    }
+    Ok(lhs)
+}
+
+/// Parses an operand followed by any binary operators, grouped by their
+/// precedence and associativity.
+pub(super) fn parse_binary(tokens: &mut TokenCursor) -> Result<Spanned<Expression>, ParseError> {
+    parse_binary_within(tokens, None)
+}
+
+/// Parses a prefix `-` or `!`, which binds tighter than any binary
+/// operator: `-a * b` is `(-a) * b`.
+pub(super) fn parse_unary(tokens: &mut TokenCursor) -> Result<Spanned<Expression>, ParseError> {
+    let token = tokens.peek();
+    let op = match UnaryOp::lookup(&token.value) {
+        Some(op) if token.kind == TokenKind::Operator => op,
+        _ => {
+            return Err(ParseError::unexpected(token, vec![Expected::Token("-"), Expected::Token("!")]));
+        }
+    };
+    let start = tokens.advance().span;
+    let operand = match op {
+        // a literal the sign applies to directly can be the most negative
+        // value of its type, but not one that is called or indexed first
+        UnaryOp::Negate if tokens.peek().kind == TokenKind::Integer => {
+            let checkpoint = tokens.checkpoint();
+            let literal = number::Integer::parse_negated(tokens)?;
+            match Expression::at_postfix(tokens) {
+                true => {
+                    tokens.rewind(checkpoint);
+                    Expression::parse_operand(tokens)?
+                }
+                false => literal.map(Expression::Integer),
+            }
+        }
+        _ => Expression::parse_operand(tokens)?,
+    };
+    let span = start.to(operand.span);
+    Ok(Spanned::new(Expression::Unary { op, operand: Box::new(operand) }, span))
+}
+
+#[cfg(test)]
+mod test_operator {
+    use super::*;
+    use crate::parse::{identifier, integer, parse_expression as parse};
+    use crate::parse::number::Integer;
+    use crate::lexer::{self, IntegerSuffix};
+
+    fn binary(op: BinaryOp, lhs: Spanned<Expression>, rhs: Spanned<Expression>) -> Spanned<Expression> {
+        Expression::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) }.into()
+    }
+
+    fn unary(op: UnaryOp, operand: Spanned<Expression>) -> Spanned<Expression> {
+        Expression::Unary { op, operand: Box::new(operand) }.into()
+    }
+
+    #[test]
+    fn looks_up_every_binary_operator() {
+        for op in BinaryOp::ALL {
+            assert_eq!(BinaryOp::lookup(op.as_str()), Some(op));
+        }
+    }
+
+    #[test]
+    fn binds_multiplication_tighter_than_addition() {
+        assert_eq!(parse("a + b * c"), Ok(binary(
+            BinaryOp::Add,
+            identifier("a"),
+            binary(BinaryOp::Multiply, identifier("b"), identifier("c")),
+        )));
+    }
+
+    #[test]
+    fn groups_arithmetic_to_the_left() {
+        assert_eq!(parse("a - b - c"), Ok(binary(
+            BinaryOp::Subtract,
+            binary(BinaryOp::Subtract, identifier("a"), identifier("b")),
+            identifier("c"),
+        )));
+    }
+
+    #[test]
+    fn binds_comparison_tighter_than_logic() {
+        assert_eq!(parse("a < b || c == d && e"), Ok(binary(
+            BinaryOp::Or,
+            binary(BinaryOp::Less, identifier("a"), identifier("b")),
+            binary(
+                BinaryOp::And,
+                binary(BinaryOp::Equal, identifier("c"), identifier("d")),
+                identifier("e"),
+            ),
+        )));
+    }
+
+    #[test]
+    fn binds_unary_operators_tightest() {
+        assert_eq!(parse("-a * b"), Ok(binary(
+            BinaryOp::Multiply,
+            unary(UnaryOp::Negate, identifier("a")),
+            identifier("b"),
+        )));
+        assert_eq!(parse("!!done"), Ok(unary(UnaryOp::Not, unary(UnaryOp::Not, identifier("done")))));
+        assert_eq!(parse("a*-b"), Ok(binary(
+            BinaryOp::Multiply,
+            identifier("a"),
+            unary(UnaryOp::Negate, identifier("b")),
+        )));
+    }
+
+    #[test]
+    fn negates_the_most_negative_integer_of_each_type() {
+        let negated = |integer: Integer| unary(UnaryOp::Negate, Expression::Integer(integer).into());
+        assert_eq!(parse("-128i8"), Ok(negated(Integer::new(128, Some(IntegerSuffix::I8)))));
+        assert_eq!(parse("-9223372036854775808"), Ok(negated(Integer::from(1 << 63))));
+        assert_eq!(parse("-9223372036854775808i64 - 1"), Ok(binary(
+            BinaryOp::Subtract,
+            negated(Integer::new(1 << 63, Some(IntegerSuffix::I64))),
+            integer(1),
+        )));
+    }
+
+    #[test]
+    fn returns_error_for_negated_integers_out_of_range() {
+        let error = parse("-129i8").unwrap_err();
+        assert_eq!(error.to_string(), "1:2: Literal '129i8' is out of range for i8");
+        let error = parse("-128i8.0").unwrap_err();
+        assert_eq!(error.to_string(), "1:2: Literal '128i8' is out of range for i8");
+        let error = parse("9223372036854775808").unwrap_err();
+        assert_eq!(error.to_string(), "1:1: Literal '9223372036854775808' is out of range for i64");
+    }
+
+    #[test]
+    fn spans_from_first_operand_to_last() {
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new("-a + bc"));
+        let expression = Expression::parse(&mut tokens).unwrap();
+        assert_eq!((expression.span.start, expression.span.end), (0, 7));
+        match expression.node {
+            Expression::Binary { lhs, .. } => assert_eq!((lhs.span.start, lhs.span.end), (0, 2)),
+            _ => panic!("expected a binary expression"),
+        }
+    }
+
+    #[test]
+    fn returns_error_for_chained_comparisons() {
+        let error = parse("a < b < c").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::ChainedOperators { first: "<".to_string(), second: "<".to_string() });
+        assert_eq!((error.line, error.column), (1, 7));
+    }
+
+    #[test]
+    fn returns_error_for_missing_right_operand() {
+        assert_eq!(parse("a +").map_err(|error| error.kind), Err(ParseErrorKind::UnexpectedEof { expected: vec![Expected::Expression] }));
+    }
+}
@@ -0,0 +1,154 @@
+// parenthesized:
+//     '(' expression ')'
+//
+// tuple:
+//     '(' ')' | '(' expression ',' (expression (',' expression)* ','?)? ')'
+//
+// After an error in an element, parsing resumes at the next ',' or ')'
+// outside of brackets.
+//
+// operand:
+//     primary ('.' decimal | arguments | subscript)*
+
+use crate::lexer::TokenKind;
+
+use super::{next_element, parse_element, Expected, Expression, LiteralError, ParseError, ParseErrorKind, Spanned, TokenCursor};
+
+/// Parses a parenthesized expression, which is the expression itself
+/// spanning its parentheses, or a tuple. A trailing comma makes a tuple of
+/// one element, as in `(a,)`. A bad element is reported and skipped,
+/// leaving the others.
+pub(super) fn parse_parenthesized(tokens: &mut TokenCursor) -> Result<Spanned<Expression>, ParseError> {
+    let start = tokens.expect_operator("(")?.span;
+    if tokens.at_operator(")") {
+        let end = tokens.advance().span;
+        return Ok(Spanned::new(Expression::Tuple(Vec::new()), start.to(end)));
+    }
+
+    let mut elements = vec![parse_element(tokens, ")")?];
+    let mut is_tuple = false;
+    while next_element(tokens, ")")? {
+        is_tuple = true;
+        if tokens.at_operator(")") {
+            break;
+        }
+        elements.push(parse_element(tokens, ")")?);
+    }
+    let span = start.to(tokens.advance().span);
+    match is_tuple {
+        true => Ok(Spanned::new(Expression::Tuple(elements), span)),
+        false => Ok(Spanned::new(elements.remove(0).node, span)),
+    }
+}
+
+/// Whether a tuple index follows: a `.` written right against the number
+/// after it, so that `t.0` is an index but `f . 0` is an operator.
+pub(super) fn at_index(tokens: &TokenCursor) -> bool {
+    let dot = tokens.peek();
+    let index = tokens.peek_nth(1);
+    tokens.at_operator(".")
+        && matches!(index.kind, TokenKind::Integer | TokenKind::Float)
+        && dot.span.end == index.span.start
+}
+
+/// Parses the index after `tuple`. The lexer reads `t.0.1` as `t`, `.` and
+/// the float `0.1`, so a float stands for two indices.
+pub(super) fn parse_index(tokens: &mut TokenCursor, tuple: Spanned<Expression>) -> Result<Spanned<Expression>, ParseError> {
+    tokens.expect_operator(".")?;
+    let token = tokens.peek();
+    if !matches!(token.kind, TokenKind::Integer | TokenKind::Float) {
+        return Err(ParseError::unexpected(token, vec![Expected::Integer]));
+    }
+    let token = tokens.advance();
+
+    let mut expression = tuple;
+    for digits in token.value.split('.') {
+        if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            let kind = ParseErrorKind::InvalidLiteral { literal: token.value.to_string(), reason: LiteralError::Malformed };
+            return Err(ParseError::at(kind, &token));
+        }
+        let index = match digits.parse::<usize>() {
+            Ok(index) => index,
+            Err(_) => {
+                let kind = ParseErrorKind::InvalidLiteral { literal: token.value.to_string(), reason: LiteralError::OutOfRange("usize") };
+                return Err(ParseError::at(kind, &token));
+            }
+        };
+        let span = expression.span.to(token.span);
+        expression = Spanned::new(Expression::TupleIndex { tuple: Box::new(expression), index }, span);
+    }
+    Ok(expression)
+}
+
+#[cfg(test)]
+mod test_tuple {
+    use super::*;
+    use crate::parse::{identifier, parse_expression};
+    use crate::lexer;
+    use crate::parse::operator::BinaryOp;
+
+    fn parse(input: &str) -> Result<Expression, ParseError> {
+        parse_expression(input).map(|expression| expression.node)
+    }
+
+    fn index(tuple: Expression, index: usize) -> Expression {
+        Expression::TupleIndex { tuple: Box::new(tuple.into()), index }
+    }
+
+    #[test]
+    fn groups_a_parenthesized_expression() {
+        let sum = Expression::Binary { op: BinaryOp::Add, lhs: Box::new(identifier("a")), rhs: Box::new(identifier("b")) };
+        assert_eq!(parse("(a + b) * c"), Ok(Expression::Binary {
+            op: BinaryOp::Multiply,
+            lhs: Box::new(sum.into()),
+            rhs: Box::new(identifier("c")),
+        }));
+    }
+
+    #[test]
+    fn parses_tuples() {
+        assert_eq!(parse("()"), Ok(Expression::Tuple(vec![])));
+        assert_eq!(parse("(a,)"), Ok(Expression::Tuple(vec![identifier("a")])));
+        assert_eq!(parse("(a, b, c,)"), Ok(Expression::Tuple(vec![identifier("a"), identifier("b"), identifier("c")])));
+        assert_eq!(parse("(\n  a,\n  b\n)"), Ok(Expression::Tuple(vec![identifier("a"), identifier("b")])));
+    }
+
+    #[test]
+    fn tells_lambdas_from_tuples() {
+        assert!(matches!(parse("(a b) => (a, b)"), Ok(Expression::Lambda(_))));
+        assert!(matches!(parse("(a)"), Ok(Expression::Identifier(_))));
+    }
+
+    #[test]
+    fn indexes_tuples() {
+        assert_eq!(parse("t.0"), Ok(index(Expression::Identifier("t".into()), 0)));
+        assert_eq!(parse("t.1.0"), Ok(index(index(Expression::Identifier("t".into()), 1), 0)));
+        assert_eq!(parse("(a, b).1"), Ok(index(Expression::Tuple(vec![identifier("a"), identifier("b")]), 1)));
+    }
+
+    #[test]
+    fn binds_indices_tighter_than_unary_operators() {
+        assert!(matches!(parse("-t.0"), Ok(Expression::Unary { .. })));
+    }
+
+    #[test]
+    fn spans_the_parentheses() {
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new("(a + b)"));
+        let expression = Expression::parse(&mut tokens).unwrap();
+        assert_eq!((expression.span.start, expression.span.end), (0, 7));
+        let (mut tokens, _) = TokenCursor::lex(lexer::Lexer::new("t.0.1"));
+        let expression = Expression::parse(&mut tokens).unwrap();
+        assert_eq!((expression.span.start, expression.span.end), (0, 5));
+    }
+
+    #[test]
+    fn returns_error_for_malformed_index() {
+        let error = parse("t.1e3").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidLiteral { literal: "1e3".to_string(), reason: LiteralError::Malformed });
+        let error = parse("(a b)").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedToken {
+            expected: vec![Expected::Token(","), Expected::Token(")")],
+            found: "b".to_string(),
+        });
+    }
+}